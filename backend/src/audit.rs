@@ -0,0 +1,166 @@
+//! Utils for the audit log of admin actions.
+//!
+//! Every action an admin takes on a paper is recorded in the `audit_log` table along with a diff of the paper's fields, inside the same transaction as the action itself.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::qp::AdminDashboardQP;
+
+/// An action recorded in the audit log
+pub enum AuditAction {
+    /// A paper's details were edited (or the paper was approved/unapproved)
+    Edit,
+    /// A paper was soft-deleted
+    Delete,
+    /// A paper was soft-deleted because it was replaced by another paper during an edit
+    Replace,
+    /// A paper was permanently deleted from the trash
+    HardDelete,
+    /// A paper was restored from the trash
+    Restore,
+    /// An uploaded paper was rejected by a reviewer (and moved to the trash)
+    Reject,
+    /// Duplicate papers were merged into a paper. Recorded both for the paper that was kept (with its changed details) and for each duplicate (moved to the trash).
+    Merge,
+    /// The pages of a paper were rotated, deleted, or reordered
+    EditPages,
+    /// A new paper was created from some pages of another paper
+    ExtractPages,
+    /// A tag on the paper was renamed or deleted. Tags added or removed using the edit endpoints are recorded as edits.
+    Tag,
+}
+
+impl From<&AuditAction> for String {
+    fn from(value: &AuditAction) -> Self {
+        match value {
+            AuditAction::Edit => "edit".into(),
+            AuditAction::Delete => "delete".into(),
+            AuditAction::Replace => "replace".into(),
+            AuditAction::HardDelete => "hard_delete".into(),
+            AuditAction::Restore => "restore".into(),
+            AuditAction::Reject => "reject".into(),
+            AuditAction::Merge => "merge".into(),
+            AuditAction::EditPages => "edit_pages".into(),
+            AuditAction::ExtractPages => "extract_pages".into(),
+            AuditAction::Tag => "tag".into(),
+        }
+    }
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+/// An entry in the audit log
+pub struct AuditLogEntry {
+    pub id: i32,
+    /// The username of the admin who took the action
+    pub username: String,
+    /// The action taken (see [`AuditAction`])
+    pub action: String,
+    /// The id of the paper the action was taken on
+    pub paper_id: Option<i32>,
+    pub timestamp: chrono::NaiveDateTime,
+    /// The fields of the paper that changed, as an object of the form `{ "field": { "before": .., "after": .. } }`
+    pub diff: Value,
+}
+
+/// Returns the diff of two versions of a paper (serialized as JSON), containing only the fields that changed. Either version can be `null` (eg: when a paper is hard-deleted).
+///
+/// The diff is an object of the form `{ "field": { "before": .., "after": .. } }`.
+pub fn diff(before: &Value, after: &Value) -> Value {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+
+    let mut diff = Map::new();
+    for key in before.keys().chain(after.keys()) {
+        let before_value = before.get(key).unwrap_or(&Value::Null);
+        let after_value = after.get(key).unwrap_or(&Value::Null);
+
+        if before_value != after_value && !diff.contains_key(key) {
+            diff.insert(
+                key.to_owned(),
+                serde_json::json!({ "before": before_value, "after": after_value }),
+            );
+        }
+    }
+
+    Value::Object(diff)
+}
+
+/// Serializes a version of a paper to be used with [`diff`]. `None` is serialized as `null`.
+pub fn snapshot(paper: Option<&AdminDashboardQP>) -> Result<Value, serde_json::Error> {
+    serde_json::to_value(paper)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn only_changed_fields_are_included() {
+        let before = json!({ "id": 1, "year": 2023, "exam": "midsem", "note": "" });
+        let after = json!({ "id": 1, "year": 2024, "exam": "midsem", "note": "Set B" });
+
+        assert_eq!(
+            diff(&before, &after),
+            json!({
+                "year": { "before": 2023, "after": 2024 },
+                "note": { "before": "", "after": "Set B" },
+            })
+        );
+    }
+
+    #[test]
+    fn identical_versions_have_an_empty_diff() {
+        let paper = json!({ "id": 1, "tags": ["open book"] });
+
+        assert_eq!(diff(&paper, &paper), json!({}));
+    }
+
+    #[test]
+    fn null_versions_diff_every_field() {
+        let paper = json!({ "id": 1, "year": 2024 });
+
+        assert_eq!(
+            diff(&paper, &Value::Null),
+            json!({
+                "id": { "before": 1, "after": null },
+                "year": { "before": 2024, "after": null },
+            })
+        );
+        assert_eq!(
+            diff(&Value::Null, &paper),
+            json!({
+                "id": { "before": null, "after": 1 },
+                "year": { "before": null, "after": 2024 },
+            })
+        );
+    }
+
+    #[test]
+    fn fields_missing_from_one_version_are_null() {
+        let before = json!({ "id": 1, "claimed_by": "alice" });
+        let after = json!({ "id": 1, "merged_into": 7 });
+
+        assert_eq!(
+            diff(&before, &after),
+            json!({
+                "claimed_by": { "before": "alice", "after": null },
+                "merged_into": { "before": null, "after": 7 },
+            })
+        );
+    }
+
+    #[test]
+    fn nested_values_are_compared_whole() {
+        let before = json!({ "tags": ["a", "b"] });
+        let after = json!({ "tags": ["a", "c"] });
+
+        assert_eq!(
+            diff(&before, &after),
+            json!({ "tags": { "before": ["a", "b"], "after": ["a", "c"] } })
+        );
+    }
+}
@@ -1,26 +1,86 @@
 //! Utils for Github OAuth integration and JWT authentication
 //!
-//! Currently this is only used in the admin dashboard and uses Github OAuth for authentication
+//! Currently this is only used in the admin dashboard and uses Github OAuth for authentication. Each admin has a [`Role`] which decides the endpoints they can use.
 
 use std::collections::BTreeMap;
 
 use color_eyre::eyre::{eyre, Context, ContextCompat};
 use http::StatusCode;
 use jwt::{Claims, RegisteredClaims, SignWithKey, VerifyWithKey};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::env::EnvVars;
+use crate::{db::Database, env::EnvVars};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// The role of an admin. Each role can do everything the roles before it can.
+///
+/// Can be converted to and parsed from a String using the [`From`] and [`TryFrom`] trait implementations.
+pub enum Role {
+    /// Can view papers in the admin dashboard, parsed from `viewer`
+    Viewer,
+    /// Can edit, approve, reject, revert, soft-delete, and restore papers, parsed from `reviewer`
+    Reviewer,
+    /// Can also permanently delete papers and view the audit log, parsed from `maintainer`
+    Maintainer,
+    /// Can also manage the roles of other admins, parsed from `owner`
+    Owner,
+}
+
+impl TryFrom<&str> for Role {
+    type Error = color_eyre::eyre::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "viewer" => Ok(Role::Viewer),
+            "reviewer" => Ok(Role::Reviewer),
+            "maintainer" => Ok(Role::Maintainer),
+            "owner" => Ok(Role::Owner),
+            _ => Err(eyre!("Error parsing role: Invalid value.")),
+        }
+    }
+}
+
+impl From<&Role> for String {
+    fn from(value: &Role) -> Self {
+        match value {
+            Role::Viewer => "viewer".into(),
+            Role::Reviewer => "reviewer".into(),
+            Role::Maintainer => "maintainer".into(),
+            Role::Owner => "owner".into(),
+        }
+    }
+}
+
+impl Serialize for Role {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(String::from(self).as_str())
+    }
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+/// The role of an admin, as stored in the database
+pub struct AdminRole {
+    pub username: String,
+    pub role: Role,
+    /// The username of the owner who assigned the role. `None` if the role was assigned on the admin's first login.
+    pub assigned_by: Option<String>,
+    pub updated_at: chrono::NaiveDateTime,
+}
 
 #[derive(Clone)]
 /// Struct containing the auth information of a user
 pub struct Auth {
     pub jwt: String,
     pub username: String,
+    pub role: Role,
 }
 
 /// Verifies whether a JWT is valid and signed with the secret key
 ///
-/// Returns the username and jwt in a struct
+/// Returns the username, role, and jwt in a struct
 pub async fn verify_token(
     token: String,
     env_vars: &EnvVars,
@@ -36,16 +96,24 @@ pub async fn verify_token(
     let username = username
         .as_str()
         .ok_or(eyre!("Username is not a string."))?;
+    let role = claims
+        .private
+        .get("role")
+        .ok_or(eyre!("Role not in the claims."))?
+        .as_str()
+        .ok_or(eyre!("Role is not a string."))?;
 
     Ok(Auth {
         jwt: token,
         username: username.to_owned(),
+        role: Role::try_from(role)?,
     })
 }
 
-/// Generates a JWT with the username (for claims) and secret key
+/// Generates a JWT with the username and role (for claims) and secret key
 async fn generate_token(
     username: &str,
+    role: Role,
     env_vars: &EnvVars,
 ) -> Result<String, color_eyre::eyre::Error> {
     let jwt_key = env_vars.get_jwt_key()?;
@@ -61,6 +129,7 @@ async fn generate_token(
         "username".into(),
         serde_json::Value::String(username.into()),
     );
+    private_claims.insert("role".into(), serde_json::Value::String((&role).into()));
 
     let claims = Claims {
         registered: RegisteredClaims {
@@ -97,11 +166,13 @@ struct GithubMembershipResponse {
 /// 1. Uses the OAuth code to get an access token.
 /// 2. Uses the access token to get the user's username.
 /// 3. Uses the username and an admin's access token to verify whether the user is a member of the admins github team, or the admin themselves.
+/// 4. Gets the user's role from the database. The first time a user logs in, admins in `GH_ADMIN_USERNAMES` are made owners and team members are made reviewers.
 ///
 /// Returns the JWT if the user is authenticated, `None` otherwise.
 pub async fn authenticate_user(
     code: &str,
     env_vars: &EnvVars,
+    db: &Database,
 ) -> Result<Option<String>, color_eyre::eyre::Error> {
     let client = reqwest::Client::new();
 
@@ -158,7 +229,8 @@ pub async fn authenticate_user(
         .split(",")
         .any(|x| x == username)
     {
-        return Ok(Some(generate_token(&username, env_vars).await?));
+        let role = db.get_or_assign_role(&username, Role::Owner).await?;
+        return Ok(Some(generate_token(&username, role, env_vars).await?));
     }
 
     // Check the user's membership in the team
@@ -194,6 +266,7 @@ pub async fn authenticate_user(
     if state != "active" {
         Ok(None)
     } else {
-        Ok(Some(generate_token(&username, env_vars).await?))
+        let role = db.get_or_assign_role(&username, Role::Reviewer).await?;
+        Ok(Some(generate_token(&username, role, env_vars).await?))
     }
 }
@@ -0,0 +1,299 @@
+//! The course registry, containing the canonical name and details of each course.
+//!
+//! Course names are free text when a paper is uploaded, so the same course can be named in many ways. When a paper is uploaded or edited, its course name is replaced with the registered name of its course code (see [`check_course`]) and any mismatch is flagged for the reviewers.
+
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+/// A course in the registry. Also the format of a row in the CSV file imported using [`parse_csv`].
+pub struct Course {
+    /// The course code, stored in uppercase and without whitespace (see [`normalize_course_code`])
+    pub code: String,
+    /// The canonical name of the course
+    pub name: String,
+    #[serde(default)]
+    /// The department offering the course, used as the department of its papers if it matches the code or name of a department (see [`crate::departments`])
+    pub department: String,
+    #[serde(default)]
+    pub credits: Option<i32>,
+    #[serde(default)]
+    /// The first year the course was offered. `None` if not known.
+    pub first_year: Option<i32>,
+    #[serde(default)]
+    /// The last year the course was offered. `None` if it is still offered (or not known).
+    pub last_year: Option<i32>,
+}
+
+impl Course {
+    /// Normalizes the course's code and trims its other fields. Returns an error if a required field is empty or the active years are invalid.
+    pub fn normalize(self) -> Result<Self, color_eyre::eyre::Error> {
+        let course = Self {
+            code: normalize_course_code(&self.code),
+            name: self.name.trim().to_owned(),
+            department: self.department.trim().to_owned(),
+            ..self
+        };
+
+        if course.code.is_empty() {
+            Err(eyre!("The course code cannot be empty."))
+        } else if course.name.is_empty() {
+            Err(eyre!("The course name cannot be empty."))
+        } else if let (Some(first_year), Some(last_year)) = (course.first_year, course.last_year) {
+            if first_year > last_year {
+                Err(eyre!(
+                    "The first year ({}) is after the last year ({}).",
+                    first_year,
+                    last_year
+                ))
+            } else {
+                Ok(course)
+            }
+        } else {
+            Ok(course)
+        }
+    }
+
+    /// Returns whether the course was offered in the given year. Unknown years are assumed to be open-ended.
+    fn is_active_in(&self, year: i32) -> bool {
+        self.first_year.is_none_or(|first_year| first_year <= year)
+            && self.last_year.is_none_or(|last_year| year <= last_year)
+    }
+}
+
+/// Normalizes a course code: converts it to uppercase and removes any whitespace. Course codes are always looked up in the registry by their normalized codes.
+pub fn normalize_course_code(code: &str) -> String {
+    code.split_whitespace().collect::<String>().to_uppercase()
+}
+
+/// Returns a course name with only its lowercase alphanumeric characters, used to compare course names ignoring case, whitespace, and punctuation
+fn comparable_name(name: &str) -> String {
+    name.chars()
+        .filter(|character| character.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Checks a paper's course details against the registered course of its course code (`None` if the code is not registered).
+///
+/// Returns the course code and name to be stored (the registered ones, if the course is registered) and a message for the reviewers if the details do not match the registry: the code is not registered, the given name differs from the registered name, or the course was not offered in the paper's year. An empty name is filled in without a message.
+pub fn check_course(
+    course: Option<&Course>,
+    course_code: String,
+    course_name: String,
+    year: i32,
+) -> (String, String, Option<String>) {
+    let Some(course) = course else {
+        let mismatch = format!(
+            "The course code `{}` is not in the course registry.",
+            course_code
+        );
+        return (course_code, course_name, Some(mismatch));
+    };
+
+    let mut mismatches = Vec::<String>::new();
+    if !course_name.trim().is_empty()
+        && comparable_name(&course_name) != comparable_name(&course.name)
+    {
+        mismatches.push(format!(
+            "The course name `{}` was replaced with the registered name `{}`.",
+            course_name.trim(),
+            course.name
+        ));
+    }
+
+    if !course.is_active_in(year) {
+        mismatches.push(format!(
+            "The course was not offered in {} (offered {}-{}).",
+            year,
+            course
+                .first_year
+                .map(|year| year.to_string())
+                .unwrap_or_default(),
+            course
+                .last_year
+                .map(|year| year.to_string())
+                .unwrap_or_default()
+        ));
+    }
+
+    let mismatch = (!mismatches.is_empty()).then(|| mismatches.join(" "));
+
+    (course.code.clone(), course.name.clone(), mismatch)
+}
+
+/// Parses a CSV file of courses. The first row must be a header with the columns `code`, `name`, `department`, `credits`, `first_year`, and `last_year` (in any order). Only `code` and `name` are required, empty values are treated as unknown.
+///
+/// Returns the normalized courses, or an error message for every invalid row.
+pub fn parse_csv(data: &str) -> Result<Vec<Course>, Vec<String>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(data.as_bytes());
+
+    let mut courses = Vec::<Course>::new();
+    let mut errors = Vec::<String>::new();
+    for (index, row) in reader.deserialize::<Course>().enumerate() {
+        // The header is line 1
+        let line = index + 2;
+
+        match row
+            .map_err(color_eyre::eyre::Error::from)
+            .and_then(Course::normalize)
+        {
+            Ok(course) => courses.push(course),
+            Err(e) => errors.push(format!("Line {}: {}", line, e)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(courses)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn course(first_year: Option<i32>, last_year: Option<i32>) -> Course {
+        Course {
+            code: "CS10003".into(),
+            name: "Programming and Data Structures".into(),
+            department: "Computer Science and Engineering".into(),
+            credits: Some(4),
+            first_year,
+            last_year,
+        }
+    }
+
+    #[test]
+    fn course_codes_are_normalized() {
+        assert_eq!(normalize_course_code(" cs 10003\t"), "CS10003");
+    }
+
+    #[test]
+    fn csv_columns_can_be_in_any_order() {
+        let courses = parse_csv(
+            "name, code, last_year, first_year, credits, department
+            Programming and Data Structures, cs 10003, , 2018, 4, CSE
+            Physics,PH11003,2022,,,",
+        )
+        .unwrap();
+
+        assert_eq!(courses.len(), 2);
+
+        assert_eq!(courses[0].code, "CS10003");
+        assert_eq!(courses[0].name, "Programming and Data Structures");
+        assert_eq!(courses[0].department, "CSE");
+        assert_eq!(courses[0].credits, Some(4));
+        assert_eq!(courses[0].first_year, Some(2018));
+        assert_eq!(courses[0].last_year, None);
+
+        assert_eq!(courses[1].code, "PH11003");
+        assert_eq!(courses[1].department, "");
+        assert_eq!(courses[1].credits, None);
+        assert_eq!(courses[1].first_year, None);
+        assert_eq!(courses[1].last_year, Some(2022));
+    }
+
+    #[test]
+    fn csv_optional_columns_can_be_omitted() {
+        let courses = parse_csv("code,name\nMA11003,Mathematics").unwrap();
+
+        assert_eq!(courses.len(), 1);
+        assert_eq!(courses[0].code, "MA11003");
+        assert_eq!(courses[0].department, "");
+        assert_eq!(courses[0].credits, None);
+    }
+
+    #[test]
+    fn csv_errors_are_reported_for_every_invalid_row() {
+        let Err(errors) = parse_csv(
+            "code,name,first_year,last_year
+            CS10003,Programming,,
+            ,Unknown,,
+            PH11003,,,
+            MA11003,Mathematics,2020,2019
+            EE11003,Electrical,twenty,",
+        ) else {
+            panic!("invalid rows were parsed");
+        };
+
+        assert_eq!(errors.len(), 4);
+        assert_eq!(errors[0], "Line 3: The course code cannot be empty.");
+        assert_eq!(errors[1], "Line 4: The course name cannot be empty.");
+        assert_eq!(
+            errors[2],
+            "Line 5: The first year (2020) is after the last year (2019)."
+        );
+        assert!(errors[3].starts_with("Line 6: "));
+    }
+
+    #[test]
+    fn unregistered_courses_are_flagged() {
+        let (code, name, mismatch) =
+            check_course(None, "XX10001".into(), "Unknown Course".into(), 2024);
+
+        assert_eq!(code, "XX10001");
+        assert_eq!(name, "Unknown Course");
+        assert_eq!(
+            mismatch.as_deref(),
+            Some("The course code `XX10001` is not in the course registry.")
+        );
+    }
+
+    #[test]
+    fn registered_names_replace_the_given_names() {
+        let course = course(None, None);
+
+        // Case, whitespace, and punctuation are ignored
+        let (code, name, mismatch) = check_course(
+            Some(&course),
+            "CS10003".into(),
+            "programming and  data-structures.".into(),
+            2024,
+        );
+        assert_eq!(code, "CS10003");
+        assert_eq!(name, "Programming and Data Structures");
+        assert_eq!(mismatch, None);
+
+        // Empty names are filled in
+        let (_, name, mismatch) = check_course(Some(&course), "CS10003".into(), " ".into(), 2024);
+        assert_eq!(name, "Programming and Data Structures");
+        assert_eq!(mismatch, None);
+
+        let (_, name, mismatch) =
+            check_course(Some(&course), "CS10003".into(), "Programming".into(), 2024);
+        assert_eq!(name, "Programming and Data Structures");
+        assert_eq!(
+            mismatch.as_deref(),
+            Some("The course name `Programming` was replaced with the registered name `Programming and Data Structures`.")
+        );
+    }
+
+    #[test]
+    fn papers_outside_the_active_years_are_flagged() {
+        let course = course(Some(2018), Some(2022));
+        let name = "Programming and Data Structures".to_string();
+
+        for year in [2018, 2020, 2022] {
+            let (_, _, mismatch) =
+                check_course(Some(&course), "CS10003".into(), name.clone(), year);
+            assert_eq!(mismatch, None);
+        }
+
+        let (_, _, mismatch) = check_course(Some(&course), "CS10003".into(), name.clone(), 2023);
+        assert_eq!(
+            mismatch.as_deref(),
+            Some("The course was not offered in 2023 (offered 2018-2022).")
+        );
+
+        let course = self::course(None, Some(2022));
+        let (_, _, mismatch) = check_course(Some(&course), "CS10003".into(), name, 2023);
+        assert_eq!(
+            mismatch.as_deref(),
+            Some("The course was not offered in 2023 (offered -2022).")
+        );
+    }
+}
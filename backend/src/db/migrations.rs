@@ -0,0 +1,225 @@
+//! Migrations that bring an existing database up to date with the schema in `INIT_DB` (see [`super::queries`]).
+//!
+//! The pending migrations are applied in order when the backend starts (see [`super::Database::new`]) and the applied migrations are recorded in the `schema_migrations` table. Every migration is idempotent, so it can also be applied to a database initialized with the latest `INIT_DB`.
+//!
+//! New columns and tables must be added to both `INIT_DB` and a new migration at the end of [`MIGRATIONS`]. Existing migrations must never be changed.
+
+/// A database migration
+pub struct Migration {
+    /// A unique name of the migration, recorded in the `schema_migrations` table once it is applied
+    pub name: &'static str,
+    /// The SQL statements of the migration
+    pub query: &'static str,
+}
+
+/// All the migrations, in the order they are applied
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        name: "0001_quarantine",
+        query: "ALTER TABLE iqps ADD COLUMN IF NOT EXISTS is_quarantined BOOLEAN NOT NULL DEFAULT FALSE;",
+    },
+    Migration {
+        name: "0002_upload_jobs",
+        query: "CREATE TABLE IF NOT EXISTS upload_jobs (
+    id integer primary key GENERATED ALWAYS AS identity,
+    atomic BOOLEAN NOT NULL DEFAULT FALSE,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at TIMESTAMP
+);
+CREATE TABLE IF NOT EXISTS upload_job_files (
+    id integer primary key GENERATED ALWAYS AS identity,
+    job_id INTEGER NOT NULL REFERENCES upload_jobs(id) ON DELETE CASCADE,
+    filename TEXT NOT NULL,
+    file_details JSONB,
+    content_type TEXT NOT NULL DEFAULT '',
+    staged_file TEXT NOT NULL DEFAULT '',
+    status TEXT NOT NULL DEFAULT 'pending',
+    message TEXT NOT NULL DEFAULT ''
+);
+CREATE INDEX IF NOT EXISTS idx_upload_job_files_job_id ON upload_job_files (job_id);",
+    },
+    Migration {
+        name: "0003_upload_job_tokens",
+        query: "ALTER TABLE upload_jobs ADD COLUMN IF NOT EXISTS token TEXT NOT NULL UNIQUE DEFAULT gen_random_uuid()::text;",
+    },
+    Migration {
+        name: "0004_multi_file_uploads",
+        query: "ALTER TABLE upload_job_files ADD COLUMN IF NOT EXISTS content_types TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE upload_job_files ADD COLUMN IF NOT EXISTS staged_files TEXT[] NOT NULL DEFAULT '{}';
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'upload_job_files' AND column_name = 'staged_file') THEN
+        UPDATE upload_job_files SET content_types = ARRAY[content_type], staged_files = ARRAY[staged_file] WHERE staged_file <> '';
+        ALTER TABLE upload_job_files DROP COLUMN content_type, DROP COLUMN staged_file;
+    END IF;
+END $$;",
+    },
+    Migration {
+        name: "0005_audit_log",
+        query: "CREATE TABLE IF NOT EXISTS audit_log (
+    id integer primary key GENERATED ALWAYS AS identity,
+    username TEXT NOT NULL,
+    action TEXT NOT NULL,
+    paper_id INTEGER,
+    timestamp TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    diff JSONB NOT NULL DEFAULT '{}'
+);
+CREATE INDEX IF NOT EXISTS idx_audit_log_paper_id ON audit_log (paper_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_username ON audit_log (username);
+CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log (timestamp);",
+    },
+    Migration {
+        name: "0006_paper_history",
+        query: "CREATE TABLE IF NOT EXISTS iqps_history (
+    id integer primary key GENERATED ALWAYS AS identity,
+    paper_id INTEGER NOT NULL,
+    course_code TEXT NOT NULL,
+    course_name TEXT NOT NULL,
+    year INTEGER NOT NULL,
+    semester TEXT NOT NULL,
+    exam TEXT NOT NULL,
+    note TEXT NOT NULL,
+    approve_status BOOLEAN NOT NULL,
+    filelink TEXT NOT NULL,
+    edited_by TEXT,
+    edited_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_iqps_history_paper_id ON iqps_history (paper_id);",
+    },
+    Migration {
+        name: "0007_admin_roles",
+        query: "CREATE TABLE IF NOT EXISTS admin_roles (
+    username TEXT PRIMARY KEY,
+    role TEXT NOT NULL,
+    assigned_by TEXT,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);",
+    },
+    Migration {
+        name: "0008_trash",
+        query: "ALTER TABLE iqps ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+ALTER TABLE iqps ADD COLUMN IF NOT EXISTS deleted_by TEXT;
+UPDATE iqps SET deleted_at=CURRENT_TIMESTAMP WHERE is_deleted AND deleted_at IS NULL;",
+    },
+    Migration {
+        name: "0009_claims",
+        query: "ALTER TABLE iqps ADD COLUMN IF NOT EXISTS claimed_by TEXT;
+ALTER TABLE iqps ADD COLUMN IF NOT EXISTS claimed_until TIMESTAMP;",
+    },
+    Migration {
+        name: "0010_paper_versions",
+        query: "ALTER TABLE iqps ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;",
+    },
+    Migration {
+        name: "0011_rejections",
+        query: "ALTER TABLE iqps ADD COLUMN IF NOT EXISTS reject_reason TEXT;
+ALTER TABLE iqps ADD COLUMN IF NOT EXISTS reject_note TEXT;
+ALTER TABLE upload_job_files ADD COLUMN IF NOT EXISTS paper_id INTEGER;",
+    },
+    Migration {
+        name: "0012_paper_contents",
+        query: "ALTER TABLE iqps ADD COLUMN IF NOT EXISTS content_hash TEXT;
+ALTER TABLE iqps ADD COLUMN IF NOT EXISTS content_text TEXT NOT NULL DEFAULT '';
+CREATE INDEX IF NOT EXISTS idx_content_hash ON iqps (content_hash);",
+    },
+    Migration {
+        name: "0013_duplicate_scans",
+        query: "CREATE TABLE IF NOT EXISTS duplicate_scans (
+    id integer primary key GENERATED ALWAYS AS identity,
+    status TEXT NOT NULL DEFAULT 'pending',
+    started_by TEXT NOT NULL,
+    paper_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at TIMESTAMP
+);
+CREATE TABLE IF NOT EXISTS duplicate_clusters (
+    id integer primary key GENERATED ALWAYS AS identity,
+    scan_id INTEGER NOT NULL REFERENCES duplicate_scans(id) ON DELETE CASCADE,
+    keeper_id INTEGER NOT NULL,
+    paper_ids INTEGER[] NOT NULL,
+    reasons TEXT[] NOT NULL DEFAULT '{}'
+);
+CREATE INDEX IF NOT EXISTS idx_duplicate_clusters_scan_id ON duplicate_clusters (scan_id);",
+    },
+    Migration {
+        name: "0014_merges",
+        query: "ALTER TABLE iqps ADD COLUMN IF NOT EXISTS merged_into INTEGER;",
+    },
+    Migration {
+        name: "0015_replacements",
+        query: "ALTER TABLE iqps ADD COLUMN IF NOT EXISTS replaced_by INTEGER;",
+    },
+    Migration {
+        name: "0016_approval_times",
+        query: "ALTER TABLE iqps ADD COLUMN IF NOT EXISTS approved_at TIMESTAMP;",
+    },
+    Migration {
+        name: "0017_file_revisions",
+        query: "ALTER TABLE iqps ADD COLUMN IF NOT EXISTS file_revision INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE iqps_history ADD COLUMN IF NOT EXISTS file_revision INTEGER NOT NULL DEFAULT 0;",
+    },
+    Migration {
+        name: "0018_attachments",
+        query: "ALTER TABLE iqps ADD COLUMN IF NOT EXISTS attachment_of INTEGER;
+ALTER TABLE iqps ADD COLUMN IF NOT EXISTS attachment_kind TEXT;
+CREATE INDEX IF NOT EXISTS idx_attachment_of ON iqps (attachment_of);",
+    },
+    Migration {
+        name: "0019_tags",
+        query: "CREATE TABLE IF NOT EXISTS tags (
+    id integer primary key GENERATED ALWAYS AS identity,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT '',
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE TABLE IF NOT EXISTS paper_tags (
+    paper_id INTEGER NOT NULL REFERENCES iqps(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (paper_id, tag_id)
+);
+CREATE INDEX IF NOT EXISTS idx_paper_tags_tag_id ON paper_tags (tag_id);",
+    },
+    Migration {
+        name: "0020_courses",
+        query: "ALTER TABLE iqps ADD COLUMN IF NOT EXISTS course_mismatch TEXT;
+CREATE TABLE IF NOT EXISTS courses (
+    code TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    department TEXT NOT NULL DEFAULT '',
+    credits INTEGER,
+    first_year INTEGER,
+    last_year INTEGER
+);",
+    },
+    Migration {
+        name: "0021_departments",
+        query: "CREATE TABLE IF NOT EXISTS departments (
+    code TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    prefixes TEXT[] NOT NULL DEFAULT '{}'
+);
+INSERT INTO departments (code, name, prefixes) VALUES
+    ('AE', 'Aerospace Engineering', '{AE}'),
+    ('AG', 'Agricultural and Food Engineering', '{AG}'),
+    ('AR', 'Architecture and Regional Planning', '{AR}'),
+    ('BT', 'Biotechnology', '{BT}'),
+    ('CE', 'Civil Engineering', '{CE}'),
+    ('CH', 'Chemical Engineering', '{CH}'),
+    ('CS', 'Computer Science and Engineering', '{CS}'),
+    ('CY', 'Chemistry', '{CY}'),
+    ('EC', 'Electronics and Electrical Communication Engineering', '{EC}'),
+    ('EE', 'Electrical Engineering', '{EE}'),
+    ('GG', 'Geology and Geophysics', '{GG}'),
+    ('HS', 'Humanities and Social Sciences', '{HS}'),
+    ('IM', 'Industrial and Systems Engineering', '{IM}'),
+    ('MA', 'Mathematics', '{MA}'),
+    ('ME', 'Mechanical Engineering', '{ME}'),
+    ('MI', 'Mining Engineering', '{MI}'),
+    ('MT', 'Metallurgical and Materials Engineering', '{MT}'),
+    ('NA', 'Ocean Engineering and Naval Architecture', '{NA}'),
+    ('PH', 'Physics', '{PH}')
+ON CONFLICT (code) DO NOTHING;",
+    },
+];
@@ -1,16 +1,26 @@
 //! Database stuff. See submodules also.
 
+use chrono::NaiveDateTime;
 use color_eyre::eyre::eyre;
-use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgPool, Postgres, Transaction};
+use serde_json::Value;
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, types::Json, PgPool, Postgres, Transaction};
 use std::time::Duration;
 
 use crate::{
+    audit::{self, AuditAction, AuditLogEntry},
+    auth::{AdminRole, Role},
+    courses::{self, Course},
+    departments::{Department, DepartmentDetails},
+    duplicates::{CandidateRow, DuplicateCluster, DuplicateScan, ScanPaper},
     env::EnvVars,
     pathutils::{PaperCategory, Paths},
-    qp::{self, AdminDashboardQP, Exam, LibraryQP, Semester},
-    routing::{EditReq, FileDetails},
+    qp::{self, AdminDashboardQP, Exam, LibraryQP, PaperVersion, RejectReason, Semester},
+    routing::{EditReq, FileDetails, PaperListReq, StagedFile, UploadJob, UploadStatus},
+    stats::{CountStats, Stats, StatsPeriod},
+    tags::Tag,
 };
 
+mod migrations;
 mod models;
 mod queries;
 
@@ -43,19 +53,102 @@ impl Database {
             .connect(&database_url)
             .await?;
 
-        Ok(Self {
+        let database = Self {
             connection: conn_pool,
-        })
+        };
+        database.run_migrations().await?;
+
+        Ok(database)
     }
 
-    /// Fetches the list of all unapproved papers
-    pub async fn get_unapproved_papers(&self) -> Result<Vec<qp::AdminDashboardQP>, sqlx::Error> {
-        let query_sql = queries::get_all_unapproved_query();
-        let papers: Vec<qp::AdminDashboardQP> = sqlx::query_as(&query_sql)
+    /// Applies the pending [`migrations::MIGRATIONS`] in order, each in its own transaction
+    async fn run_migrations(&self) -> Result<(), sqlx::Error> {
+        sqlx::query(queries::INIT_MIGRATIONS)
+            .execute(&self.connection)
+            .await?;
+
+        let applied: Vec<String> = sqlx::query_scalar(queries::GET_APPLIED_MIGRATIONS)
             .fetch_all(&self.connection)
             .await?;
 
-        Ok(papers)
+        for migration in migrations::MIGRATIONS {
+            if applied.iter().any(|name| name == migration.name) {
+                continue;
+            }
+
+            let mut tx = self.connection.begin().await?;
+            sqlx::raw_sql(migration.query).execute(&mut *tx).await?;
+            sqlx::query(queries::INSERT_APPLIED_MIGRATION)
+                .bind(migration.name)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+
+            tracing::info!("Applied database migration `{}`.", migration.name);
+        }
+
+        Ok(())
+    }
+
+    /// Gets a page of the unapproved papers, or the soft-deleted papers (trash) if `deleted` is set, matching the filters in the request.
+    ///
+    /// Returns the papers, the number of papers matching the filters, and the total number of papers in the list (without the filters).
+    pub async fn get_paper_list(
+        &self,
+        deleted: bool,
+        req: &PaperListReq,
+    ) -> Result<(Vec<AdminDashboardQP>, i64, i64), sqlx::Error> {
+        let filters = [
+            req.from_library.is_some(),
+            req.course_code.is_some(),
+            req.year.is_some(),
+            req.exam.is_some(),
+            req.uploaded_from.is_some(),
+            req.uploaded_to.is_some(),
+        ];
+
+        let list_query_sql = queries::get_paper_list_query(deleted, filters, req.newest_first);
+        let count_query_sql = queries::get_paper_list_count_query(deleted, filters);
+        let mut list_query = sqlx::query_as(&list_query_sql);
+        let mut count_query = sqlx::query_scalar(&count_query_sql);
+
+        if let Some(from_library) = req.from_library {
+            list_query = list_query.bind(from_library);
+            count_query = count_query.bind(from_library);
+        }
+        if let Some(course_code) = &req.course_code {
+            list_query = list_query.bind(course_code);
+            count_query = count_query.bind(course_code);
+        }
+        if let Some(year) = req.year {
+            list_query = list_query.bind(year);
+            count_query = count_query.bind(year);
+        }
+        if let Some(exam) = &req.exam {
+            list_query = list_query.bind(String::from(exam));
+            count_query = count_query.bind(String::from(exam));
+        }
+        if let Some(uploaded_from) = req.uploaded_from {
+            list_query = list_query.bind(uploaded_from);
+            count_query = count_query.bind(uploaded_from);
+        }
+        if let Some(uploaded_to) = req.uploaded_to {
+            list_query = list_query.bind(uploaded_to);
+            count_query = count_query.bind(uploaded_to);
+        }
+
+        let papers: Vec<AdminDashboardQP> = list_query
+            .bind(req.per_page)
+            .bind((req.page - 1) * req.per_page)
+            .fetch_all(&self.connection)
+            .await?;
+        let count: i64 = count_query.fetch_one(&self.connection).await?;
+        let total_count: i64 =
+            sqlx::query_scalar(&queries::get_paper_list_count_query(deleted, [false; 6]))
+                .fetch_one(&self.connection)
+                .await?;
+
+        Ok((papers, count, total_count))
     }
 
     /// Returns the number of unapproved papers
@@ -67,20 +160,110 @@ impl Database {
         Ok(count.0)
     }
 
+    /// Gets the statistics about the archive and the review queue. The timeline and the median approval time cover the periods between `from` and `to`.
+    pub async fn get_stats(
+        &self,
+        period: &StatsPeriod,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> Result<Stats, sqlx::Error> {
+        let queue_size = self.get_unapproved_papers_count().await?;
+
+        let timeline = sqlx::query_as(queries::GET_STATS_TIMELINE)
+            .bind(String::from(period))
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.connection)
+            .await?;
+
+        let median_approval_seconds: Option<f64> =
+            sqlx::query_scalar(queries::GET_MEDIAN_APPROVAL_SECONDS)
+                .bind(from)
+                .bind(to)
+                .fetch_one(&self.connection)
+                .await?;
+
+        let approvals_by_reviewer = sqlx::query_as(queries::GET_APPROVALS_BY_REVIEWER)
+            .fetch_all(&self.connection)
+            .await?;
+
+        Ok(Stats {
+            queue_size,
+            timeline,
+            median_approval_seconds,
+            approvals_by_reviewer,
+            by_source: self
+                .get_counts(&queries::get_published_count_query(
+                    "CASE WHEN from_library THEN 'library' ELSE 'uploaded' END",
+                    "key",
+                ))
+                .await?,
+            by_exam: self
+                .get_counts(&queries::get_published_count_query(
+                    "exam",
+                    "count DESC, key",
+                ))
+                .await?,
+            by_year: self
+                .get_counts(&queries::get_published_count_query(
+                    "year::TEXT",
+                    "key DESC",
+                ))
+                .await?,
+            by_department: self
+                .get_counts(&queries::get_published_count_by_department_query())
+                .await?,
+        })
+    }
+
+    /// Runs a query that returns counts grouped by a key
+    async fn get_counts(&self, query_sql: &str) -> Result<Vec<CountStats>, sqlx::Error> {
+        sqlx::query_as(query_sql).fetch_all(&self.connection).await
+    }
+
     /// Searches for papers from a given query. Uses some voodoo black magic by @rajivharlalka
+    ///
+    /// If `tag_filter` is not empty, only papers with all the given tags are returned. If `department_filter` is set, only papers of the given department are returned.
     pub async fn search_papers(
         &self,
         query: &str,
         exam_filter: Vec<Exam>,
+        tag_filter: &[String],
+        department_filter: Option<&str>,
     ) -> Result<Vec<qp::BaseQP>, sqlx::Error> {
-        let query_sql = queries::get_qp_search_query(exam_filter);
+        let query_sql = queries::get_qp_search_query(
+            exam_filter,
+            !tag_filter.is_empty(),
+            department_filter.is_some(),
+        );
         let query = sqlx::query_as(&query_sql).bind(query);
+        let query = if tag_filter.is_empty() {
+            query
+        } else {
+            query.bind(tag_filter)
+        };
+        let query = if let Some(department) = department_filter {
+            query.bind(department)
+        } else {
+            query
+        };
 
         let papers: Vec<qp::BaseQP> = query.fetch_all(&self.connection).await?;
 
         Ok(papers)
     }
 
+    /// Gets the published attachments of the papers with the given ids
+    pub async fn get_published_attachments(
+        &self,
+        paper_ids: &[i32],
+    ) -> Result<Vec<qp::Attachment>, sqlx::Error> {
+        sqlx::query_as(queries::GET_PUBLISHED_ATTACHMENTS)
+            .bind(paper_ids)
+            .fetch_all(&self.connection)
+            .await
+    }
+
     pub async fn get_paper_by_id(&self, id: i32) -> Result<qp::AdminDashboardQP, sqlx::Error> {
         let query_sql = queries::get_get_paper_by_id_query();
         let query = sqlx::query_as(&query_sql).bind(id);
@@ -90,22 +273,69 @@ impl Database {
         Ok(paper)
     }
 
-    /// Edit's a paper's details.
+    /// Gets the published paper with the given id. If the paper was replaced by (or merged into) another paper, its replacement is returned instead.
+    ///
+    /// Returns `None` if the paper does not exist or is not published.
+    pub async fn resolve_paper(&self, id: i32) -> Result<Option<qp::BaseQP>, sqlx::Error> {
+        let query_sql = queries::get_resolve_paper_query();
+
+        sqlx::query_as(&query_sql)
+            .bind(id)
+            .fetch_optional(&self.connection)
+            .await
+    }
+
+    /// Gets a paper by id inside a transaction. Returns `None` if the paper does not exist.
+    async fn get_paper_by_id_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: i32,
+    ) -> Result<Option<qp::AdminDashboardQP>, sqlx::Error> {
+        let query_sql = queries::get_get_paper_by_id_query();
+
+        sqlx::query_as(&query_sql)
+            .bind(id)
+            .fetch_optional(&mut **tx)
+            .await
+    }
+
+    /// Gets a paper by id inside a transaction and locks it until the transaction ends, so that it is not changed by other requests in the meantime. Returns `None` if the paper does not exist.
+    pub async fn lock_paper_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: i32,
+    ) -> Result<Option<qp::AdminDashboardQP>, sqlx::Error> {
+        let query_sql = queries::get_lock_paper_query();
+
+        sqlx::query_as(&query_sql)
+            .bind(id)
+            .fetch_optional(&mut **tx)
+            .await
+    }
+
+    /// Edits a paper's details inside the given transaction. Used to edit one or more papers in a single transaction. The edit is recorded in the audit log with the given action.
     ///
     /// - Sets the `approved_by` field to the username if approved.
     /// - Sets the `filelink` to:
-    ///     - For library papers, remains unchanged
+    ///     - For library papers, remains unchanged (until their file is rewritten)
     ///     - For uploaded papers, approved papers are moved to the approved directory and renamed `id_coursecode_coursename_year_semester_exam.pdf` and unapproved papers are moved to the unapproved directory and named `id.pdf`
-    /// - Deletes `replace` papers from the database.
+    ///     - Approved attachments are moved to the attachments directory and renamed `id_kind_paperid_coursecode_year_semester_exam.pdf`
+    ///     - If `new_file_revision` is set, the file revision is incremented and appended to the filename (`_r2`), so that the current file is kept for reverting
+    /// - Replaces the course name with the registered name of the course code and flags any mismatch with the course registry (see [`courses::check_course`]).
+    /// - Deletes `replace` papers from the database. Their attachments are moved to the edited paper.
+    /// - Saves the new details as a version of the paper (see [`Database::get_paper_history`]).
+    /// - Records the edit (and the replaced papers) in the audit log.
+    /// - If `version` is set, the paper is only edited if it has not changed since that version.
     ///
-    /// Returns the database transaction, the old filelink and the new paper details ([`crate::qp::AdminDashboardQP`])
-    pub async fn edit_paper(
+    /// Returns the old filelink and the new paper details ([`crate::qp::AdminDashboardQP`]). The transaction must be rolled back if an error is returned.
+    pub async fn edit_paper_in_tx(
         &self,
+        tx: &mut Transaction<'_, Postgres>,
         edit_req: EditReq,
         username: &str,
+        action: AuditAction,
         env_vars: &EnvVars,
-    ) -> Result<(Transaction<'_, Postgres>, String, AdminDashboardQP), color_eyre::eyre::Error>
-    {
+    ) -> Result<(String, AdminDashboardQP), color_eyre::eyre::Error> {
         let EditReq {
             id,
             course_code,
@@ -116,14 +346,28 @@ impl Database {
             approve_status,
             note,
             replace,
+            version,
+            new_file_revision,
+            add_tags,
+            remove_tags,
+            ..
         } = edit_req;
 
-        let current_details = self.get_paper_by_id(id).await?;
+        let current_details = self
+            .get_paper_by_id_in_tx(tx, id)
+            .await?
+            .ok_or(eyre!("Error: Paper {} does not exist.", id))?;
+        let before = audit::snapshot(Some(&current_details))?;
 
         // Construct the final values to be inserted into the db
         let course_code = course_code.unwrap_or(current_details.qp.course_code);
         let course_name = course_name.unwrap_or(current_details.qp.course_name);
         let year = year.unwrap_or(current_details.qp.year);
+        let registered_course = self
+            .get_course_in_tx(tx, &courses::normalize_course_code(&course_code))
+            .await?;
+        let (course_code, course_name, course_mismatch) =
+            courses::check_course(registered_course.as_ref(), course_code, course_name, year);
         let semester: String = String::from(
             &semester
                 .map(|sem| Semester::try_from(sem.as_str()))
@@ -136,33 +380,83 @@ impl Database {
                 .transpose()?
                 .unwrap_or(current_details.qp.exam),
         );
+        let note = note.unwrap_or(current_details.qp.note);
         let approve_status = approve_status.unwrap_or(current_details.approve_status);
 
         // Set the new filelink
         let old_filelink = current_details.qp.filelink;
+        let file_revision = if new_file_revision {
+            current_details.file_revision + 1
+        } else {
+            current_details.file_revision
+        };
+        let revision_suffix = if file_revision > 0 {
+            format!("_r{}", file_revision)
+        } else {
+            "".into()
+        };
 
-        let new_filelink = if current_details.qp.from_library {
+        let new_filelink = if current_details.qp.from_library && file_revision == 0 {
             old_filelink.clone()
+        } else if let (true, Some(attachment_of), Some(attachment_kind)) = (
+            approve_status,
+            current_details.attachment_of,
+            &current_details.attachment_kind,
+        ) {
+            env_vars.paths.get_slug(
+                &format!(
+                    "{}.pdf",
+                    Paths::sanitize_path(&format!(
+                        "{}_{}_{}_{}_{}_{}_{}{}",
+                        id,
+                        attachment_kind,
+                        attachment_of,
+                        course_code,
+                        year,
+                        semester,
+                        exam,
+                        revision_suffix
+                    ))
+                ),
+                PaperCategory::Attachment,
+            )
         } else if approve_status {
             env_vars.paths.get_slug(
                 &format!(
                     "{}.pdf",
                     Paths::sanitize_path(&format!(
-                        "{}_{}_{}_{}_{}_{}",
-                        id, course_code, course_name, year, semester, exam
+                        "{}_{}_{}_{}_{}_{}{}",
+                        id, course_code, course_name, year, semester, exam, revision_suffix
                     ))
                 ),
                 PaperCategory::Approved,
             )
         } else {
-            env_vars
-                .paths
-                .get_slug(&format!("{}.pdf", id), PaperCategory::Unapproved)
+            env_vars.paths.get_unapproved_slug(id, file_revision)
         };
 
-        let mut tx = self.connection.begin().await?;
+        sqlx::query(queries::INSERT_ORIGINAL_PAPER_VERSION)
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+
+        // Change the tags before the edit so that the edited paper (and the audit log) has the new tags
+        if !add_tags.is_empty() {
+            sqlx::query(queries::ADD_PAPER_TAGS)
+                .bind(id)
+                .bind(&add_tags)
+                .execute(&mut **tx)
+                .await?;
+        }
+        if !remove_tags.is_empty() {
+            sqlx::query(queries::REMOVE_PAPER_TAGS)
+                .bind(id)
+                .bind(&remove_tags)
+                .execute(&mut **tx)
+                .await?;
+        }
 
-        let query_sql = queries::get_edit_paper_query(approve_status);
+        let query_sql = queries::get_edit_paper_query(approve_status, version.is_some());
         let query = sqlx::query_as(&query_sql)
             .bind(id)
             .bind(&course_code)
@@ -172,7 +466,9 @@ impl Database {
             .bind(&exam)
             .bind(&note)
             .bind(approve_status)
-            .bind(&new_filelink);
+            .bind(&new_filelink)
+            .bind(file_revision)
+            .bind(&course_mismatch);
 
         let query = if approve_status {
             query.bind(username)
@@ -180,68 +476,338 @@ impl Database {
             query
         };
 
-        let new_qp: AdminDashboardQP = query.fetch_one(&mut *tx).await?;
+        let query = if let Some(version) = version {
+            query.bind(version)
+        } else {
+            query
+        };
+
+        let new_qp: AdminDashboardQP = query.fetch_optional(&mut **tx).await?.ok_or(eyre!(
+            "Error: Paper {} is deleted or was changed during the edit.",
+            id
+        ))?;
+
+        sqlx::query(queries::INSERT_PAPER_VERSION)
+            .bind(id)
+            .bind(username)
+            .execute(&mut **tx)
+            .await?;
+
+        self.insert_audit_log(
+            tx,
+            username,
+            action,
+            id,
+            &audit::diff(&before, &audit::snapshot(Some(&new_qp))?),
+        )
+        .await?;
 
-        // Delete the replaced papers
+        // Delete the replaced papers, their attachments now belong to this paper
         for replace_id in replace {
-            let rows_affected = sqlx::query(queries::SOFT_DELETE_ANY_BY_ID)
+            let rows_affected = self
+                .soft_delete_in_tx(tx, replace_id, username, AuditAction::Replace, Some(id))
+                .await?;
+
+            if rows_affected > 1 {
+                return Err(eyre!(
+                    "Error: {} (> 1) papers were deleted. Rolling back.",
+                    rows_affected
+                ));
+            }
+
+            sqlx::query(queries::MOVE_ATTACHMENTS)
                 .bind(replace_id)
+                .bind(id)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        Ok((old_filelink, new_qp))
+    }
+
+    /// Merges duplicate papers into a paper (the keeper) in a single transaction.
+    ///
+    /// - Edits the keeper's details using the edit request (see [`Database::edit_paper_in_tx`]).
+    /// - If `file_source_id` is a duplicate, copies its content hash and extracted text to the keeper. The file itself must be copied by the caller.
+    /// - Soft-deletes the duplicates, pointing their `merged_into` field to the keeper. Their attachments are moved to the keeper.
+    /// - Records the merge in the audit log for the keeper and each duplicate.
+    ///
+    /// Returns the database transaction, the keeper's old filelink, and its new details. The transaction must be committed by the caller.
+    pub async fn merge_papers(
+        &self,
+        edit_req: EditReq,
+        duplicate_ids: &[i32],
+        file_source_id: i32,
+        username: &str,
+        env_vars: &EnvVars,
+    ) -> Result<(Transaction<'_, Postgres>, String, AdminDashboardQP), color_eyre::eyre::Error>
+    {
+        let keeper_id = edit_req.id;
+        let mut tx = self.connection.begin().await?;
+
+        let (old_filelink, new_qp) = self
+            .edit_paper_in_tx(&mut tx, edit_req, username, AuditAction::Merge, env_vars)
+            .await?;
+
+        if file_source_id != keeper_id {
+            sqlx::query(queries::COPY_PAPER_CONTENT)
+                .bind(keeper_id)
+                .bind(file_source_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for &duplicate_id in duplicate_ids {
+            let before = self.get_paper_by_id_in_tx(&mut tx, duplicate_id).await?;
+
+            let rows_affected = sqlx::query(queries::MERGE_INTO_BY_ID)
+                .bind(duplicate_id)
+                .bind(username)
+                .bind(keeper_id)
                 .execute(&mut *tx)
                 .await?
                 .rows_affected();
 
-            if rows_affected > 1 {
-                tx.rollback().await?;
+            if rows_affected != 1 {
                 return Err(eyre!(
-                    "Error: {} (> 1) papers were deleted. Rolling back.",
-                    rows_affected
+                    "Error: Paper {} is deleted or does not exist.",
+                    duplicate_id
                 ));
             }
+
+            sqlx::query(queries::MOVE_ATTACHMENTS)
+                .bind(duplicate_id)
+                .bind(keeper_id)
+                .execute(&mut *tx)
+                .await?;
+
+            let after = self.get_paper_by_id_in_tx(&mut tx, duplicate_id).await?;
+            self.insert_audit_log(
+                &mut tx,
+                username,
+                AuditAction::Merge,
+                duplicate_id,
+                &audit::diff(
+                    &audit::snapshot(before.as_ref())?,
+                    &audit::snapshot(after.as_ref())?,
+                ),
+            )
+            .await?;
         }
 
         Ok((tx, old_filelink, new_qp))
     }
 
-    /// Sets the `is_deleted` field to true and `approve_status` to false. Only deletes uploaded papers.
+    /// Sets the `is_deleted` field to true and `approve_status` to false. Only deletes uploaded papers. The paper's attachments are deleted along with it. The deletions are recorded in the audit log under the given username.
     ///
     /// Returns a boolean that represents whether a db entry was affected or not. If more than one entry was affected, an error will be thrown and the transaction will be rolled back.
-    pub async fn soft_delete(&self, id: i32) -> Result<bool, color_eyre::eyre::Error> {
+    pub async fn soft_delete(
+        &self,
+        id: i32,
+        username: &str,
+    ) -> Result<bool, color_eyre::eyre::Error> {
         let mut tx = self.connection.begin().await?;
 
+        let rows_affected = self
+            .soft_delete_in_tx(&mut tx, id, username, AuditAction::Delete, None)
+            .await?;
+
+        if rows_affected > 1 {
+            tx.rollback().await?;
+            return Err(eyre!(
+                "Error: {} (> 1) papers were deleted. Rolling back.",
+                rows_affected
+            ));
+        }
+
+        if rows_affected == 1 {
+            let attachment_ids: Vec<i32> = sqlx::query_scalar(queries::GET_LIVE_ATTACHMENT_IDS)
+                .bind(id)
+                .fetch_all(&mut *tx)
+                .await?;
+
+            for attachment_id in attachment_ids {
+                self.soft_delete_in_tx(&mut tx, attachment_id, username, AuditAction::Delete, None)
+                    .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(rows_affected == 1)
+    }
+
+    /// Soft-deletes a paper inside the given transaction and records it in the audit log with the given action. `replaced_by` is the id of the paper replacing the deleted paper, if any.
+    ///
+    /// Returns the number of rows affected.
+    async fn soft_delete_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: i32,
+        username: &str,
+        action: AuditAction,
+        replaced_by: Option<i32>,
+    ) -> Result<u64, color_eyre::eyre::Error> {
+        let before = self.get_paper_by_id_in_tx(tx, id).await?;
+
         let rows_affected = sqlx::query(queries::SOFT_DELETE_ANY_BY_ID)
             .bind(id)
+            .bind(username)
+            .bind(replaced_by)
+            .execute(&mut **tx)
+            .await?
+            .rows_affected();
+
+        if rows_affected == 1 {
+            let after = self.get_paper_by_id_in_tx(tx, id).await?;
+
+            self.insert_audit_log(
+                tx,
+                username,
+                action,
+                id,
+                &audit::diff(
+                    &audit::snapshot(before.as_ref())?,
+                    &audit::snapshot(after.as_ref())?,
+                ),
+            )
+            .await?;
+        }
+
+        Ok(rows_affected)
+    }
+
+    /// Rejects an unapproved paper, moving it to the trash with the reason for the rejection. The rejection is recorded in the audit log under the given username.
+    ///
+    /// Returns whether the paper was rejected. Papers that do not exist, are approved, or are already deleted can not be rejected.
+    pub async fn reject_paper(
+        &self,
+        id: i32,
+        username: &str,
+        reason: &RejectReason,
+        note: &str,
+    ) -> Result<bool, color_eyre::eyre::Error> {
+        let mut tx = self.connection.begin().await?;
+        let before = self.get_paper_by_id_in_tx(&mut tx, id).await?;
+
+        let rows_affected = sqlx::query(queries::REJECT_BY_ID)
+            .bind(id)
+            .bind(username)
+            .bind(String::from(reason))
+            .bind(note)
             .execute(&mut *tx)
             .await?
             .rows_affected();
 
-        if rows_affected > 1 {
+        if rows_affected != 1 {
             tx.rollback().await?;
-            Err(eyre!(
-                "Error: {} (> 1) papers were deleted. Rolling back.",
-                rows_affected
-            ))
-        } else {
-            tx.commit().await?;
-            Ok(rows_affected == 1)
+            return Ok(false);
         }
+
+        let after = self.get_paper_by_id_in_tx(&mut tx, id).await?;
+        self.insert_audit_log(
+            &mut tx,
+            username,
+            AuditAction::Reject,
+            id,
+            &audit::diff(
+                &audit::snapshot(before.as_ref())?,
+                &audit::snapshot(after.as_ref())?,
+            ),
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
     }
 
-    /// Gets all soft-deleted papers from the database
-    pub async fn get_soft_deleted_papers(&self) -> Result<Vec<AdminDashboardQP>, sqlx::Error> {
-        let query_sql = queries::get_get_soft_deleted_papers_query();
-        let papers: Vec<AdminDashboardQP> = sqlx::query_as(&query_sql)
-            .fetch_all(&self.connection)
+    /// Restores a soft-deleted paper (including papers deleted by being replaced in an edit) to the unapproved papers. Uploaded papers are given the filelink of an unapproved paper (keeping their current file revision), library papers and quarantined papers keep their filelink. The restore is recorded in the audit log under the given username.
+    ///
+    /// Returns the database transaction, the old filelink and the restored paper details ([`crate::qp::AdminDashboardQP`]), or `None` if the paper does not exist or is not deleted. The file should be copied to the new filelink before the transaction is committed.
+    pub async fn restore_paper(
+        &self,
+        id: i32,
+        username: &str,
+        env_vars: &EnvVars,
+    ) -> Result<
+        Option<(Transaction<'_, Postgres>, String, AdminDashboardQP)>,
+        color_eyre::eyre::Error,
+    > {
+        let mut tx = self.connection.begin().await?;
+
+        let Some(current_details) = self.get_paper_by_id_in_tx(&mut tx, id).await? else {
+            return Ok(None);
+        };
+
+        let old_filelink = current_details.qp.filelink.clone();
+        let new_filelink = if current_details.qp.from_library || current_details.is_quarantined {
+            old_filelink.clone()
+        } else {
+            env_vars
+                .paths
+                .get_unapproved_slug(id, current_details.file_revision)
+        };
+
+        let query_sql = queries::get_restore_paper_query();
+        let restored_qp: Option<AdminDashboardQP> = sqlx::query_as(&query_sql)
+            .bind(id)
+            .bind(&new_filelink)
+            .fetch_optional(&mut *tx)
             .await?;
 
-        Ok(papers)
+        let Some(restored_qp) = restored_qp else {
+            tx.rollback().await?;
+            return Ok(None);
+        };
+
+        self.insert_audit_log(
+            &mut tx,
+            username,
+            AuditAction::Restore,
+            id,
+            &audit::diff(
+                &audit::snapshot(Some(&current_details))?,
+                &audit::snapshot(Some(&restored_qp))?,
+            ),
+        )
+        .await?;
+
+        Ok(Some((tx, old_filelink, restored_qp)))
+    }
+
+    /// Gets the ids of the papers that have been in the trash for longer than the given number of days. Attachments come first, so that they are deleted before the papers they are attached to.
+    pub async fn get_expired_trash(&self, retention_days: i32) -> Result<Vec<i32>, sqlx::Error> {
+        sqlx::query_scalar(queries::GET_EXPIRED_TRASH)
+            .bind(retention_days)
+            .fetch_all(&self.connection)
+            .await
+    }
+
+    /// Checks whether a paper has any attachments, including attachments in the trash
+    pub async fn has_attachments(&self, id: i32) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(queries::HAS_ATTACHMENTS)
+            .bind(id)
+            .fetch_one(&self.connection)
+            .await
+    }
+
+    /// Gets the filelinks of a paper's previous files (e.g. old file revisions) that are not used by the paper or any other paper anymore
+    pub async fn get_old_filelinks(&self, id: i32) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(queries::GET_OLD_FILELINKS)
+            .bind(id)
+            .fetch_all(&self.connection)
+            .await
     }
 
-    /// Permanently deletes a paper from the database
+    /// Permanently deletes a paper in the trash from the database. Papers with attachments (including attachments in the trash) are not deleted, their attachments must be hard deleted first. The deletion (and the deleted paper's details) is recorded in the audit log under the given username.
+    ///
+    /// Returns the database transaction, which must be committed by the caller, or `None` if the paper does not exist, is not in the trash, or has attachments.
     pub async fn hard_delete(
         &self,
         id: i32,
-    ) -> Result<Transaction<'_, Postgres>, color_eyre::eyre::Error> {
+        username: &str,
+    ) -> Result<Option<Transaction<'_, Postgres>>, color_eyre::eyre::Error> {
         let mut tx = self.connection.begin().await?;
+        let before = self.get_paper_by_id_in_tx(&mut tx, id).await?;
         let rows_affected = sqlx::query(queries::HARD_DELETE_BY_ID)
             .bind(id)
             .execute(&mut *tx)
@@ -255,9 +821,19 @@ impl Database {
             ));
         } else if rows_affected < 1 {
             tx.rollback().await?;
-            return Err(eyre!("Error: No papers were deleted."));
+            return Ok(None);
         }
-        Ok(tx)
+
+        self.insert_audit_log(
+            &mut tx,
+            username,
+            AuditAction::HardDelete,
+            id,
+            &audit::diff(&audit::snapshot(before.as_ref())?, &Value::Null),
+        )
+        .await?;
+
+        Ok(Some(tx))
     }
 
     /// Returns all papers that match one or more of the specified properties exactly. `course_name` is required, other properties are optional.
@@ -281,24 +857,200 @@ impl Database {
         Ok(papers)
     }
 
-    /// Inserts a new uploaded question paper into the database. Uses a placeholder for the filelink which should be replaced once the id is known using the [crate::db::Database::update_filelink] function.
-    ///
-    /// Returns a tuple with the transaction and the id of the inserted paper.
-    pub async fn insert_new_uploaded_qp<'c>(
+    /// Returns candidate duplicates of a paper, fetched using fuzzy matching (see [`crate::duplicates`]). At most `limit` unranked candidates are returned.
+    pub async fn get_similar_candidates(
         &self,
-        file_details: FileDetails,
-    ) -> Result<(Transaction<'c, Postgres>, i32), color_eyre::eyre::Error> {
-        let mut tx = self.connection.begin().await?;
+        id: i32,
+        limit: i64,
+    ) -> Result<Vec<CandidateRow>, sqlx::Error> {
+        let query_sql = queries::get_similar_candidates_query();
 
-        let FileDetails {
-            course_code,
-            course_name,
-            year,
-            exam,
-            semester,
-            note,
-            ..
-        } = file_details;
+        sqlx::query_as(&query_sql)
+            .bind(id)
+            .bind(limit)
+            .fetch_all(&self.connection)
+            .await
+    }
+
+    /// Sets the hash and the extracted text of the contents of a paper's file, used to find duplicates. Takes the same transaction used to insert the paper.
+    pub async fn set_paper_content(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: i32,
+        content_hash: &str,
+        content_text: &str,
+    ) -> Result<(), color_eyre::eyre::Error> {
+        sqlx::query(queries::SET_PAPER_CONTENT)
+            .bind(id)
+            .bind(content_hash)
+            .bind(content_text)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Gets the papers with the given ids, skipping the ones that are deleted or do not exist
+    pub async fn get_papers_by_ids(
+        &self,
+        ids: &[i32],
+    ) -> Result<Vec<AdminDashboardQP>, sqlx::Error> {
+        let query_sql = queries::get_papers_by_ids_query();
+
+        sqlx::query_as(&query_sql)
+            .bind(ids)
+            .fetch_all(&self.connection)
+            .await
+    }
+
+    /// Creates a new duplicate scan, picked up by the duplicate scan worker. Returns the id of the scan, or `None` if a scan is already pending or being processed.
+    pub async fn insert_duplicate_scan(&self, username: &str) -> Result<Option<i32>, sqlx::Error> {
+        sqlx::query_scalar(queries::INSERT_DUPLICATE_SCAN)
+            .bind(username)
+            .fetch_optional(&self.connection)
+            .await
+    }
+
+    /// Picks the oldest pending duplicate scan and marks it as processing. Returns the id of the scan, or `None` if there are no pending scans.
+    pub async fn claim_next_duplicate_scan(&self) -> Result<Option<i32>, sqlx::Error> {
+        sqlx::query_scalar(queries::CLAIM_NEXT_DUPLICATE_SCAN)
+            .fetch_optional(&self.connection)
+            .await
+    }
+
+    /// Marks duplicate scans that were being processed when the server stopped as pending, so that they are picked up again.
+    pub async fn reset_interrupted_duplicate_scans(&self) -> Result<(), sqlx::Error> {
+        sqlx::query(queries::RESET_INTERRUPTED_DUPLICATE_SCANS)
+            .execute(&self.connection)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Gets all the approved and library papers to be grouped into duplicate clusters
+    pub async fn get_duplicate_scan_papers(&self) -> Result<Vec<ScanPaper>, sqlx::Error> {
+        let query_sql = queries::get_duplicate_scan_papers_query();
+
+        sqlx::query_as(&query_sql).fetch_all(&self.connection).await
+    }
+
+    /// Gets the pairs of ids of approved and library papers with the same course code whose extracted texts have at least the given trigram similarity
+    pub async fn get_near_identical_text_pairs(
+        &self,
+        min_similarity: f32,
+    ) -> Result<Vec<(i32, i32)>, sqlx::Error> {
+        let query_sql = queries::get_near_identical_text_pairs_query();
+
+        sqlx::query_as(&query_sql)
+            .bind(min_similarity)
+            .fetch_all(&self.connection)
+            .await
+    }
+
+    /// Saves the clusters found by a duplicate scan and marks it as done
+    pub async fn finish_duplicate_scan(
+        &self,
+        scan_id: i32,
+        paper_count: i32,
+        clusters: &[DuplicateCluster],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.connection.begin().await?;
+
+        for cluster in clusters {
+            sqlx::query(queries::INSERT_DUPLICATE_CLUSTER)
+                .bind(scan_id)
+                .bind(cluster.keeper_id)
+                .bind(&cluster.paper_ids)
+                .bind(&cluster.reasons)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query(queries::FINISH_DUPLICATE_SCAN)
+            .bind(scan_id)
+            .bind("done")
+            .bind(paper_count)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await
+    }
+
+    /// Marks a duplicate scan as failed
+    pub async fn fail_duplicate_scan(&self, scan_id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query(queries::FINISH_DUPLICATE_SCAN)
+            .bind(scan_id)
+            .bind("failed")
+            .bind(0)
+            .execute(&self.connection)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Gets the latest duplicate scan along with the clusters it found. Returns `None` if there has never been a scan.
+    pub async fn get_latest_duplicate_scan(
+        &self,
+    ) -> Result<Option<(DuplicateScan, Vec<DuplicateCluster>)>, sqlx::Error> {
+        let scan: Option<DuplicateScan> = sqlx::query_as(queries::GET_LATEST_DUPLICATE_SCAN)
+            .fetch_optional(&self.connection)
+            .await?;
+
+        if let Some(scan) = scan {
+            let clusters = sqlx::query_as(queries::GET_DUPLICATE_CLUSTERS)
+                .bind(scan.id)
+                .fetch_all(&self.connection)
+                .await?;
+
+            Ok(Some((scan, clusters)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Starts a new database transaction
+    pub async fn begin_transaction(&self) -> Result<Transaction<'_, Postgres>, sqlx::Error> {
+        self.connection.begin().await
+    }
+
+    /// Inserts a new uploaded question paper into the database. Uses a placeholder for the filelink which should be replaced once the id is known using the [crate::db::Database::update_filelink] function.
+    ///
+    /// Returns a tuple with the transaction and the id of the inserted paper.
+    pub async fn insert_new_uploaded_qp<'c>(
+        &self,
+        file_details: FileDetails,
+    ) -> Result<(Transaction<'c, Postgres>, i32), color_eyre::eyre::Error> {
+        let mut tx = self.connection.begin().await?;
+        let id = self.insert_uploaded_qp(&mut tx, file_details).await?;
+
+        Ok((tx, id))
+    }
+
+    /// Inserts a new uploaded question paper into the database inside an existing transaction. Uses a placeholder for the filelink which should be replaced once the id is known using the [crate::db::Database::update_filelink] function.
+    ///
+    /// Returns the id of the inserted paper.
+    pub async fn insert_uploaded_qp(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        file_details: FileDetails,
+    ) -> Result<i32, color_eyre::eyre::Error> {
+        let FileDetails {
+            course_code,
+            course_name,
+            year,
+            exam,
+            semester,
+            note,
+            attachment_of,
+            attachment_kind,
+            ..
+        } = file_details;
+
+        let registered_course = self
+            .get_course_in_tx(tx, &courses::normalize_course_code(&course_code))
+            .await?;
+        let (course_code, course_name, course_mismatch) =
+            courses::check_course(registered_course.as_ref(), course_code, course_name, year);
 
         let query = sqlx::query_as(queries::INSERT_NEW_QP)
             .bind(course_code)
@@ -308,11 +1060,14 @@ impl Database {
             .bind(semester)
             .bind(note)
             .bind("placeholder_filelink")
-            .bind(false);
+            .bind(false)
+            .bind(attachment_of)
+            .bind(attachment_kind)
+            .bind(course_mismatch);
 
-        let Breh { id } = query.fetch_one(&mut *tx).await?;
+        let Breh { id } = query.fetch_one(&mut **tx).await?;
 
-        Ok((tx, id))
+        Ok(id)
     }
 
     /// Inserts a new library question paper into the database. Uses a placeholder for the filelink which should be replaced once the id is known using the [crate::db::Database::update_filelink] function.
@@ -364,4 +1119,548 @@ impl Database {
 
         Ok(())
     }
+
+    /// Marks a paper inserted using the [crate::db::Database::insert_new_uploaded_qp] function as quarantined. The filename is the name of the file in the quarantine directory. Takes the same transaction that the previous function used.
+    pub async fn quarantine_paper(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: i32,
+        filename: &str,
+    ) -> Result<(), color_eyre::eyre::Error> {
+        let query = sqlx::query(queries::QUARANTINE_BY_ID)
+            .bind(id)
+            .bind(filename);
+
+        query.execute(&mut **tx).await?;
+
+        Ok(())
+    }
+
+    /// Creates a new upload job inside the given transaction. Files are added to it using the [crate::db::Database::insert_upload_job_file] function.
+    ///
+    /// Returns the id of the job and the token used to check its progress.
+    pub async fn insert_upload_job(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        atomic: bool,
+    ) -> Result<(i32, String), color_eyre::eyre::Error> {
+        let job = sqlx::query_as(queries::INSERT_UPLOAD_JOB)
+            .bind(atomic)
+            .fetch_one(&mut **tx)
+            .await?;
+
+        Ok(job)
+    }
+
+    /// Adds a file to an upload job created using the [crate::db::Database::insert_upload_job] function. Takes the same transaction that the previous function used.
+    ///
+    /// `file_details` is `None` (and `status` is an error) for files that could not be matched to their details. Such files are never processed.
+    pub async fn insert_upload_job_file(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        job_id: i32,
+        file_details: Option<&FileDetails>,
+        content_types: &[String],
+        staged_files: &[String],
+        status: &UploadStatus,
+    ) -> Result<(), color_eyre::eyre::Error> {
+        sqlx::query(queries::INSERT_UPLOAD_JOB_FILE)
+            .bind(job_id)
+            .bind(&status.filename)
+            .bind(file_details.map(Json))
+            .bind(content_types)
+            .bind(staged_files)
+            .bind(&status.status)
+            .bind(&status.message)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Picks the oldest pending upload job and marks it as processing.
+    ///
+    /// Returns the id of the job and whether it is atomic, or `None` if there are no pending jobs.
+    pub async fn claim_next_upload_job(&self) -> Result<Option<(i32, bool)>, sqlx::Error> {
+        sqlx::query_as(queries::CLAIM_NEXT_UPLOAD_JOB)
+            .fetch_optional(&self.connection)
+            .await
+    }
+
+    /// Marks an upload job as done
+    pub async fn finish_upload_job(&self, job_id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query(queries::FINISH_UPLOAD_JOB)
+            .bind(job_id)
+            .execute(&self.connection)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Marks upload jobs (and their files) that were being processed when the server stopped as pending, so that they are picked up again.
+    pub async fn reset_interrupted_upload_jobs(&self) -> Result<(), sqlx::Error> {
+        let mut tx = self.connection.begin().await?;
+
+        sqlx::query(queries::RESET_INTERRUPTED_UPLOAD_JOBS)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(queries::RESET_INTERRUPTED_UPLOAD_JOB_FILES)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await
+    }
+
+    /// Gets the files of an upload job that have not been processed yet
+    pub async fn get_pending_upload_job_files(
+        &self,
+        job_id: i32,
+    ) -> Result<Vec<StagedFile>, sqlx::Error> {
+        sqlx::query_as(queries::GET_PENDING_UPLOAD_JOB_FILES)
+            .bind(job_id)
+            .fetch_all(&self.connection)
+            .await
+    }
+
+    /// Sets the status of a file in an upload job
+    pub async fn set_upload_job_file_status(
+        &self,
+        file_id: i32,
+        status: &UploadStatus,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(queries::SET_UPLOAD_JOB_FILE_STATUS)
+            .bind(file_id)
+            .bind(&status.status)
+            .bind(&status.message)
+            .bind(status.paper_id)
+            .execute(&self.connection)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sets the status of a file in an upload job inside a transaction. Used to save the status in the same transaction that inserts the file's paper, so that the file is not processed again if the worker is interrupted after the paper is inserted.
+    pub async fn set_upload_job_file_status_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        file_id: i32,
+        status: &UploadStatus,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(queries::SET_UPLOAD_JOB_FILE_STATUS)
+            .bind(file_id)
+            .bind(&status.status)
+            .bind(&status.message)
+            .bind(status.paper_id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Gets an upload job by its token along with the status of each of its files. Returns `None` if the job does not exist.
+    pub async fn get_upload_job(&self, token: &str) -> Result<Option<UploadJob>, sqlx::Error> {
+        let job: Option<UploadJob> = sqlx::query_as(queries::GET_UPLOAD_JOB)
+            .bind(token)
+            .fetch_optional(&self.connection)
+            .await?;
+
+        if let Some(mut job) = job {
+            job.files = sqlx::query_as(queries::GET_UPLOAD_JOB_FILE_STATUSES)
+                .bind(job.id)
+                .fetch_all(&self.connection)
+                .await?;
+
+            Ok(Some(job))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Records an admin action in the audit log, inside the transaction of the action itself
+    pub async fn insert_audit_log(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        username: &str,
+        action: AuditAction,
+        paper_id: i32,
+        diff: &Value,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(queries::INSERT_AUDIT_LOG)
+            .bind(username)
+            .bind(String::from(&action))
+            .bind(paper_id)
+            .bind(diff)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Gets all the courses in the registry, ordered by code. If `department_filter` is set, only the courses of the given department are returned.
+    pub async fn get_courses(
+        &self,
+        department_filter: Option<&str>,
+    ) -> Result<Vec<Course>, sqlx::Error> {
+        let query_sql = queries::get_courses_query(department_filter.is_some());
+        let query = sqlx::query_as(&query_sql);
+        let query = if let Some(department) = department_filter {
+            query.bind(department)
+        } else {
+            query
+        };
+
+        query.fetch_all(&self.connection).await
+    }
+
+    /// Gets the registered course with the given normalized code (see [`courses::normalize_course_code`]) inside a transaction
+    async fn get_course_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        code: &str,
+    ) -> Result<Option<Course>, sqlx::Error> {
+        sqlx::query_as(&queries::get_course_query())
+            .bind(code)
+            .fetch_optional(&mut **tx)
+            .await
+    }
+
+    /// Adds courses to the registry in a single transaction. Courses that are already registered are updated.
+    pub async fn upsert_courses(&self, courses: &[Course]) -> Result<(), sqlx::Error> {
+        let mut tx = self.connection.begin().await?;
+
+        for course in courses {
+            sqlx::query(queries::UPSERT_COURSE)
+                .bind(&course.code)
+                .bind(&course.name)
+                .bind(&course.department)
+                .bind(course.credits)
+                .bind(course.first_year)
+                .bind(course.last_year)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await
+    }
+
+    /// Removes a course from the registry. Returns `false` if the course is not registered.
+    pub async fn delete_course(&self, code: &str) -> Result<bool, sqlx::Error> {
+        let rows_affected = sqlx::query(queries::DELETE_COURSE)
+            .bind(code)
+            .execute(&self.connection)
+            .await?
+            .rows_affected();
+
+        Ok(rows_affected > 0)
+    }
+
+    /// Gets all the departments along with their paper and course counts, ordered by name
+    pub async fn get_departments(&self) -> Result<Vec<Department>, sqlx::Error> {
+        sqlx::query_as(&queries::get_departments_query())
+            .fetch_all(&self.connection)
+            .await
+    }
+
+    /// Adds a department, or updates it if a department with the code exists
+    pub async fn upsert_department(
+        &self,
+        department: &DepartmentDetails,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(queries::UPSERT_DEPARTMENT)
+            .bind(&department.code)
+            .bind(&department.name)
+            .bind(&department.prefixes)
+            .execute(&self.connection)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Removes a department. Returns `false` if the department does not exist.
+    pub async fn delete_department(&self, code: &str) -> Result<bool, sqlx::Error> {
+        let rows_affected = sqlx::query(queries::DELETE_DEPARTMENT)
+            .bind(code)
+            .execute(&self.connection)
+            .await?
+            .rows_affected();
+
+        Ok(rows_affected > 0)
+    }
+
+    /// Gets all the tags, ordered by name
+    pub async fn get_tags(&self) -> Result<Vec<Tag>, sqlx::Error> {
+        sqlx::query_as(&queries::get_tags_query(false))
+            .fetch_all(&self.connection)
+            .await
+    }
+
+    /// Gets the tag with the given id
+    pub async fn get_tag(&self, id: i32) -> Result<Option<Tag>, sqlx::Error> {
+        sqlx::query_as(&queries::get_tags_query(true))
+            .bind(id)
+            .fetch_optional(&self.connection)
+            .await
+    }
+
+    /// Returns the given tag names that do not exist
+    pub async fn get_unknown_tags(&self, names: &[String]) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(queries::GET_UNKNOWN_TAGS)
+            .bind(names)
+            .fetch_all(&self.connection)
+            .await
+    }
+
+    /// Creates a new tag. Returns the id of the tag, or `None` if a tag with the same name exists.
+    pub async fn insert_tag(
+        &self,
+        name: &str,
+        description: &str,
+        username: &str,
+    ) -> Result<Option<i32>, sqlx::Error> {
+        sqlx::query_scalar(queries::INSERT_TAG)
+            .bind(name)
+            .bind(description)
+            .bind(username)
+            .fetch_optional(&self.connection)
+            .await
+    }
+
+    /// Renames a tag and updates its description. The change is recorded in the audit log for every paper with the tag.
+    pub async fn update_tag(
+        &self,
+        id: i32,
+        name: &str,
+        description: &str,
+        username: &str,
+    ) -> Result<(), color_eyre::eyre::Error> {
+        let mut tx = self.connection.begin().await?;
+        let paper_ids: Vec<i32> = sqlx::query_scalar(queries::GET_TAGGED_PAPER_IDS)
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+        let before = self.get_papers_by_ids_in_tx(&mut tx, &paper_ids).await?;
+
+        sqlx::query(queries::UPDATE_TAG)
+            .bind(id)
+            .bind(name)
+            .bind(description)
+            .execute(&mut *tx)
+            .await?;
+
+        self.audit_tag_change(&mut tx, username, before).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Deletes a tag, removing it from all papers. The change is recorded in the audit log for every paper with the tag.
+    ///
+    /// Returns `false` if the tag does not exist.
+    pub async fn delete_tag(
+        &self,
+        id: i32,
+        username: &str,
+    ) -> Result<bool, color_eyre::eyre::Error> {
+        let mut tx = self.connection.begin().await?;
+        let paper_ids: Vec<i32> = sqlx::query_scalar(queries::GET_TAGGED_PAPER_IDS)
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+        let before = self.get_papers_by_ids_in_tx(&mut tx, &paper_ids).await?;
+
+        let rows_affected = sqlx::query(queries::DELETE_TAG)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        if rows_affected < 1 {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+
+        self.audit_tag_change(&mut tx, username, before).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Gets the papers with the given ids (including deleted papers) inside a transaction
+    async fn get_papers_by_ids_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        ids: &[i32],
+    ) -> Result<Vec<AdminDashboardQP>, sqlx::Error> {
+        sqlx::query_as(&queries::get_all_papers_by_ids_query())
+            .bind(ids)
+            .fetch_all(&mut **tx)
+            .await
+    }
+
+    /// Increments the versions of papers whose tags were changed by renaming or deleting a tag, and records the change in the audit log for each paper. `before` are the papers before the change.
+    async fn audit_tag_change(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        username: &str,
+        before: Vec<AdminDashboardQP>,
+    ) -> Result<(), color_eyre::eyre::Error> {
+        let ids = before.iter().map(|paper| paper.qp.id).collect::<Vec<i32>>();
+        let after: Vec<AdminDashboardQP> = sqlx::query_as(&queries::get_touch_papers_query())
+            .bind(&ids)
+            .fetch_all(&mut **tx)
+            .await?;
+
+        for paper in &before {
+            let after = after.iter().find(|after| after.qp.id == paper.qp.id);
+            self.insert_audit_log(
+                tx,
+                username,
+                AuditAction::Tag,
+                paper.qp.id,
+                &audit::diff(&audit::snapshot(Some(paper))?, &audit::snapshot(after)?),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the entries in the audit log matching the given filters, latest first. Each filter is optional.
+    pub async fn get_audit_log(
+        &self,
+        paper_id: Option<i32>,
+        username: Option<&String>,
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+        limit: i64,
+    ) -> Result<Vec<AuditLogEntry>, sqlx::Error> {
+        let query_sql = queries::get_audit_log_query(
+            paper_id.is_some(),
+            username.is_some(),
+            from.is_some(),
+            to.is_some(),
+        );
+        let query = sqlx::query_as(&query_sql).bind(limit);
+
+        let query = if let Some(paper_id) = paper_id {
+            query.bind(paper_id)
+        } else {
+            query
+        };
+        let query = if let Some(username) = username {
+            query.bind(username)
+        } else {
+            query
+        };
+        let query = if let Some(from) = from {
+            query.bind(from)
+        } else {
+            query
+        };
+        let query = if let Some(to) = to {
+            query.bind(to)
+        } else {
+            query
+        };
+
+        query.fetch_all(&self.connection).await
+    }
+
+    /// Gets all the saved versions of a paper's details, latest first. The first version of a paper is saved the first time it is edited.
+    pub async fn get_paper_history(&self, id: i32) -> Result<Vec<PaperVersion>, sqlx::Error> {
+        let query_sql = queries::get_paper_history_query();
+
+        sqlx::query_as(&query_sql)
+            .bind(id)
+            .fetch_all(&self.connection)
+            .await
+    }
+
+    /// Gets a saved version of a paper's details by the version's id
+    pub async fn get_paper_version(
+        &self,
+        version_id: i32,
+    ) -> Result<Option<PaperVersion>, sqlx::Error> {
+        let query_sql = queries::get_paper_version_query();
+
+        sqlx::query_as(&query_sql)
+            .bind(version_id)
+            .fetch_optional(&self.connection)
+            .await
+    }
+
+    /// Gets the role of an admin. If the admin does not have a role yet (first login), they are assigned the given role.
+    pub async fn get_or_assign_role(
+        &self,
+        username: &str,
+        initial_role: Role,
+    ) -> Result<Role, sqlx::Error> {
+        sqlx::query_scalar(queries::GET_OR_ASSIGN_ROLE)
+            .bind(username)
+            .bind(String::from(&initial_role))
+            .fetch_one(&self.connection)
+            .await
+    }
+
+    /// Gets the current role of an admin. Returns `None` if the admin has no role.
+    pub async fn get_admin_role(&self, username: &str) -> Result<Option<Role>, sqlx::Error> {
+        sqlx::query_scalar(queries::GET_ADMIN_ROLE)
+            .bind(username)
+            .fetch_optional(&self.connection)
+            .await
+    }
+
+    /// Gets the roles of all admins
+    pub async fn get_admin_roles(&self) -> Result<Vec<AdminRole>, sqlx::Error> {
+        sqlx::query_as(queries::GET_ADMIN_ROLES)
+            .fetch_all(&self.connection)
+            .await
+    }
+
+    /// Sets the role of an admin. The admin need not have logged in yet.
+    pub async fn set_admin_role(
+        &self,
+        username: &str,
+        role: Role,
+        assigned_by: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(queries::SET_ADMIN_ROLE)
+            .bind(username)
+            .bind(String::from(&role))
+            .bind(assigned_by)
+            .execute(&self.connection)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Claims a paper for an admin for the given number of minutes, so that other admins do not review it at the same time.
+    ///
+    /// Returns the claimed paper, or `None` if the paper does not exist, is deleted, or is claimed by another admin.
+    pub async fn claim_paper(
+        &self,
+        id: i32,
+        username: &str,
+        minutes: i32,
+    ) -> Result<Option<AdminDashboardQP>, sqlx::Error> {
+        let query_sql = queries::get_claim_paper_query();
+
+        sqlx::query_as(&query_sql)
+            .bind(id)
+            .bind(username)
+            .bind(minutes)
+            .fetch_optional(&self.connection)
+            .await
+    }
+
+    /// Releases an admin's claim on a paper. Returns whether the admin had claimed the paper.
+    pub async fn unclaim_paper(&self, id: i32, username: &str) -> Result<bool, sqlx::Error> {
+        let rows_affected = sqlx::query(queries::UNCLAIM_PAPER)
+            .bind(id)
+            .bind(username)
+            .execute(&self.connection)
+            .await?
+            .rows_affected();
+
+        Ok(rows_affected > 0)
+    }
 }
@@ -1,7 +1,10 @@
 use duplicate::duplicate_item;
 use sqlx::{postgres::PgTypeInfo, Postgres};
 
-use crate::qp::{Exam, Semester};
+use crate::{
+    auth::Role,
+    qp::{Exam, Semester},
+};
 
 // DO NOT ASK ME WHAT THE BELOW TRAIT IMPLEMENTATIONS DO
 // I JUST KNOW THEY ARE NEEDED TO TEACH SQLX HOW TO DECODE AND ENCODE THIS SHIT
@@ -19,10 +22,17 @@ impl sqlx::Type<Postgres> for Semester {
     }
 }
 
+impl sqlx::Type<Postgres> for Role {
+    fn type_info() -> <Postgres as sqlx::Database>::TypeInfo {
+        <String as sqlx::Type<Postgres>>::type_info()
+    }
+}
+
 #[duplicate_item(
     DBEncodeDecode;
     [ Exam ];
     [ Semester ];
+    [ Role ];
 )]
 impl sqlx::Decode<'_, sqlx::Postgres> for DBEncodeDecode {
     fn decode(
@@ -2,9 +2,11 @@
 //!
 //! Some of these are functions that return a query that is dynamically generated based on requirements.
 
+use std::sync::LazyLock;
+
 use crate::qp::Exam;
 
-/// Database initialization query. Not used by the backend directly.
+/// Database initialization query. Not used by the backend directly. Existing databases are updated using the [`super::migrations`], which must be kept in sync with this query.
 #[allow(dead_code)]
 const INIT_DB: &str = "
 CREATE TABLE IF NOT EXISTS iqps (
@@ -16,16 +18,163 @@ CREATE TABLE IF NOT EXISTS iqps (
     semester TEXT NOT NULL DEFAULT '',
     note TEXT NOT NULL DEFAULT '',
     filelink TEXT NOT NULL,
+    file_revision INTEGER NOT NULL DEFAULT 0,
     from_library BOOLEAN DEFAULT FALSE,
     upload_timestamp TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
     approve_status BOOLEAN DEFAULT FALSE,
     approved_by TEXT DEFAULT '',
+    approved_at TIMESTAMP,
     is_deleted BOOLEAN DEFAULT FALSE,
+    is_quarantined BOOLEAN NOT NULL DEFAULT FALSE,
+    deleted_at TIMESTAMP,
+    deleted_by TEXT,
+    claimed_by TEXT,
+    claimed_until TIMESTAMP,
+    version INTEGER NOT NULL DEFAULT 1,
+    reject_reason TEXT,
+    reject_note TEXT,
+    merged_into INTEGER,
+    replaced_by INTEGER,
+    attachment_of INTEGER,
+    attachment_kind TEXT,
+    course_mismatch TEXT,
+    content_hash TEXT,
+    content_text TEXT NOT NULL DEFAULT '',
     fts_course_details tsvector GENERATED ALWAYS AS (to_tsvector('english', course_code || ' ' || course_name)) stored
 );
 CREATE INDEX IF NOT EXISTS iqps_fts ON iqps USING gin (fts_course_details);
 CREATE EXTENSION pg_trgm;
-CREATE INDEX IF NOT EXISTS idx_course_name_trgm ON iqps USING gin (course_name gin_trgm_ops);";
+CREATE INDEX IF NOT EXISTS idx_course_name_trgm ON iqps USING gin (course_name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_content_hash ON iqps (content_hash);
+CREATE INDEX IF NOT EXISTS idx_attachment_of ON iqps (attachment_of);
+CREATE TABLE IF NOT EXISTS upload_jobs (
+    id integer primary key GENERATED ALWAYS AS identity,
+    token TEXT NOT NULL UNIQUE DEFAULT gen_random_uuid()::text,
+    atomic BOOLEAN NOT NULL DEFAULT FALSE,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at TIMESTAMP
+);
+CREATE TABLE IF NOT EXISTS upload_job_files (
+    id integer primary key GENERATED ALWAYS AS identity,
+    job_id INTEGER NOT NULL REFERENCES upload_jobs(id) ON DELETE CASCADE,
+    filename TEXT NOT NULL,
+    file_details JSONB,
+    content_types TEXT[] NOT NULL DEFAULT '{}',
+    staged_files TEXT[] NOT NULL DEFAULT '{}',
+    status TEXT NOT NULL DEFAULT 'pending',
+    message TEXT NOT NULL DEFAULT '',
+    paper_id INTEGER
+);
+CREATE INDEX IF NOT EXISTS idx_upload_job_files_job_id ON upload_job_files (job_id);
+CREATE TABLE IF NOT EXISTS audit_log (
+    id integer primary key GENERATED ALWAYS AS identity,
+    username TEXT NOT NULL,
+    action TEXT NOT NULL,
+    paper_id INTEGER,
+    timestamp TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    diff JSONB NOT NULL DEFAULT '{}'
+);
+CREATE INDEX IF NOT EXISTS idx_audit_log_paper_id ON audit_log (paper_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_username ON audit_log (username);
+CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log (timestamp);
+CREATE TABLE IF NOT EXISTS iqps_history (
+    id integer primary key GENERATED ALWAYS AS identity,
+    paper_id INTEGER NOT NULL,
+    course_code TEXT NOT NULL,
+    course_name TEXT NOT NULL,
+    year INTEGER NOT NULL,
+    semester TEXT NOT NULL,
+    exam TEXT NOT NULL,
+    note TEXT NOT NULL,
+    approve_status BOOLEAN NOT NULL,
+    filelink TEXT NOT NULL,
+    file_revision INTEGER NOT NULL DEFAULT 0,
+    edited_by TEXT,
+    edited_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_iqps_history_paper_id ON iqps_history (paper_id);
+CREATE TABLE IF NOT EXISTS admin_roles (
+    username TEXT PRIMARY KEY,
+    role TEXT NOT NULL,
+    assigned_by TEXT,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE TABLE IF NOT EXISTS duplicate_scans (
+    id integer primary key GENERATED ALWAYS AS identity,
+    status TEXT NOT NULL DEFAULT 'pending',
+    started_by TEXT NOT NULL,
+    paper_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at TIMESTAMP
+);
+CREATE TABLE IF NOT EXISTS duplicate_clusters (
+    id integer primary key GENERATED ALWAYS AS identity,
+    scan_id INTEGER NOT NULL REFERENCES duplicate_scans(id) ON DELETE CASCADE,
+    keeper_id INTEGER NOT NULL,
+    paper_ids INTEGER[] NOT NULL,
+    reasons TEXT[] NOT NULL DEFAULT '{}'
+);
+CREATE INDEX IF NOT EXISTS idx_duplicate_clusters_scan_id ON duplicate_clusters (scan_id);
+CREATE TABLE IF NOT EXISTS tags (
+    id integer primary key GENERATED ALWAYS AS identity,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT '',
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE TABLE IF NOT EXISTS paper_tags (
+    paper_id INTEGER NOT NULL REFERENCES iqps(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (paper_id, tag_id)
+);
+CREATE INDEX IF NOT EXISTS idx_paper_tags_tag_id ON paper_tags (tag_id);
+CREATE TABLE IF NOT EXISTS courses (
+    code TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    department TEXT NOT NULL DEFAULT '',
+    credits INTEGER,
+    first_year INTEGER,
+    last_year INTEGER
+);
+CREATE TABLE IF NOT EXISTS departments (
+    code TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    prefixes TEXT[] NOT NULL DEFAULT '{}'
+);
+INSERT INTO departments (code, name, prefixes) VALUES
+    ('AE', 'Aerospace Engineering', '{AE}'),
+    ('AG', 'Agricultural and Food Engineering', '{AG}'),
+    ('AR', 'Architecture and Regional Planning', '{AR}'),
+    ('BT', 'Biotechnology', '{BT}'),
+    ('CE', 'Civil Engineering', '{CE}'),
+    ('CH', 'Chemical Engineering', '{CH}'),
+    ('CS', 'Computer Science and Engineering', '{CS}'),
+    ('CY', 'Chemistry', '{CY}'),
+    ('EC', 'Electronics and Electrical Communication Engineering', '{EC}'),
+    ('EE', 'Electrical Engineering', '{EE}'),
+    ('GG', 'Geology and Geophysics', '{GG}'),
+    ('HS', 'Humanities and Social Sciences', '{HS}'),
+    ('IM', 'Industrial and Systems Engineering', '{IM}'),
+    ('MA', 'Mathematics', '{MA}'),
+    ('ME', 'Mechanical Engineering', '{ME}'),
+    ('MI', 'Mining Engineering', '{MI}'),
+    ('MT', 'Metallurgical and Materials Engineering', '{MT}'),
+    ('NA', 'Ocean Engineering and Naval Architecture', '{NA}'),
+    ('PH', 'Physics', '{PH}')
+ON CONFLICT (code) DO NOTHING;";
+
+/// Creates the table recording the applied [`super::migrations`], if it does not exist
+pub const INIT_MIGRATIONS: &str = "CREATE TABLE IF NOT EXISTS schema_migrations (
+    name TEXT PRIMARY KEY,
+    applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+)";
+
+/// Gets the names of all the applied migrations
+pub const GET_APPLIED_MIGRATIONS: &str = "SELECT name FROM schema_migrations";
+
+/// Records a migration with the given name ($1) as applied
+pub const INSERT_APPLIED_MIGRATION: &str = "INSERT INTO schema_migrations (name) VALUES ($1)";
 
 /// Query to get similar papers. Matches `course_code` ($1) always. Other parameters are optional and can be enabled or disabled using the arguments to this function.
 ///
@@ -39,7 +188,7 @@ pub fn get_similar_papers_query(year: bool, semester: bool, exam: bool) -> Strin
 
     format!(
         "SELECT {} from iqps where is_deleted=false and course_code = $1 {} {} {}",
-        ADMIN_DASHBOARD_QP_FIELDS,
+        *ADMIN_DASHBOARD_QP_FIELDS,
         if year {
             param_num += 1;
             format!("AND year=${}", param_num)
@@ -61,30 +210,153 @@ pub fn get_similar_papers_query(year: bool, semester: bool, exam: bool) -> Strin
     )
 }
 
-/// Soft deletes a paper (sets `approve_status` to false and `is_deleted` to true) of any paper.
+/// Returns a query that finds candidate duplicates of a paper ($1) along with the scores used to rank them ([`crate::duplicates::SimilarityScores`]).
+///
+/// A paper is a candidate if it has the same course code (ignoring case and whitespace), a similar course name (trigram similarity), or the same file contents (hash). At most `$2` candidates are returned, the ones most likely to be duplicates (same contents, then same course code, then most similar course name) first.
+pub fn get_similar_candidates_query() -> String {
+    format!(
+        "WITH target AS (SELECT id AS target_id, {normalized_code} AS target_code, course_name AS target_name, year AS target_year, semester AS target_semester, exam AS target_exam, content_hash AS target_hash, content_text AS target_text FROM iqps WHERE id = $1)
+        SELECT {fields},
+            {normalized_code} = target_code AS course_code_match,
+            similarity(course_name, target_name) AS name_similarity,
+            ABS(year - target_year) AS year_difference,
+            semester = target_semester AS same_semester,
+            exam = target_exam AS same_exam,
+            COALESCE(content_hash = target_hash, false) AS same_content,
+            CASE WHEN content_text <> '' AND target_text <> '' THEN similarity(content_text, target_text) ELSE 0 END AS text_similarity
+        FROM iqps, target
+        WHERE is_deleted = false AND attachment_of IS NULL AND id <> target_id AND ({normalized_code} = target_code OR course_name % target_name OR content_hash = target_hash)
+        ORDER BY same_content DESC, course_code_match DESC, name_similarity DESC, text_similarity DESC, id
+        LIMIT $2",
+        fields = *ADMIN_DASHBOARD_QP_FIELDS,
+        normalized_code = normalized_course_code("course_code")
+    )
+}
+
+/// Returns an SQL expression for the given course code column in uppercase and without whitespace, used to match course codes typed differently
+fn normalized_course_code(column: &str) -> String {
+    format!("UPPER(regexp_replace({}, '[[:space:]]', '', 'g'))", column)
+}
+
+/// Returns an SQL expression for the department code ([`crate::departments`]) of the given course code column, `NULL` if the department is not known.
+///
+/// If the course is in the registry (see [`crate::courses`]) and its registered department matches the code or the name of a department (ignoring case), that department is chosen. Otherwise, the department with the longest prefix matching the course code is chosen.
+///
+/// The column must be qualified with its table name, since the subqueries select from the `courses` and `departments` tables.
+fn department_of(column: &str) -> String {
+    format!(
+        "COALESCE(
+            (SELECT registered_department.code FROM courses AS registered_course JOIN departments AS registered_department ON registered_department.code = UPPER(TRIM(registered_course.department)) OR LOWER(registered_department.name) = LOWER(TRIM(registered_course.department)) WHERE registered_course.code = {code} ORDER BY registered_department.code LIMIT 1),
+            (SELECT department_map.code FROM departments AS department_map, unnest(department_map.prefixes) AS prefix WHERE starts_with({code}, prefix) ORDER BY LENGTH(prefix) DESC, department_map.code LIMIT 1)
+        )",
+        code = normalized_course_code(column)
+    )
+}
+
+/// Returns a query that gets all the approved and library papers ([`crate::duplicates::ScanPaper`]) to be grouped into duplicate clusters. Attachments are not included.
+///
+/// The extracted text of each paper is returned as a hash of its lowercase text without whitespace (`NULL` if there is no text) so that papers with identical text can be matched without fetching all of it.
+pub fn get_duplicate_scan_papers_query() -> String {
+    format!(
+        "SELECT id, from_library, {} AS course_code, year, semester, exam, content_hash, CASE WHEN content_text <> '' THEN md5(lower(regexp_replace(content_text, '[[:space:]]', '', 'g'))) END AS text_hash, LENGTH(content_text) AS text_length FROM iqps WHERE is_deleted = false AND attachment_of IS NULL AND (approve_status = true OR from_library = true)",
+        normalized_course_code("course_code")
+    )
+}
+
+/// Returns a query that gets the pairs of approved and library papers with the same course code whose extracted texts have a trigram similarity of at least `$1`
+pub fn get_near_identical_text_pairs_query() -> String {
+    format!(
+        "SELECT a.id, b.id FROM iqps a JOIN iqps b ON {} = {} AND a.id < b.id
+        WHERE a.is_deleted = false AND b.is_deleted = false AND a.attachment_of IS NULL AND b.attachment_of IS NULL
+            AND (a.approve_status = true OR a.from_library = true) AND (b.approve_status = true OR b.from_library = true)
+            AND a.content_text <> '' AND b.content_text <> ''
+            AND similarity(a.content_text, b.content_text) >= $1",
+        normalized_course_code("a.course_code"),
+        normalized_course_code("b.course_code")
+    )
+}
+
+/// Sets the hash ($2) and extracted text ($3) of the contents of a paper's ($1) file
+pub const SET_PAPER_CONTENT: &str = "UPDATE iqps SET content_hash=$2, content_text=$3 WHERE id=$1";
+
+/// Soft deletes a paper (sets `approve_status` to false and `is_deleted` to true) of any paper that is not already deleted. Also records the deletion time, the username of the admin ($2) who deleted it, and the id of the paper that replaced it ($3, `NULL` if it was not replaced).
 pub const SOFT_DELETE_ANY_BY_ID: &str =
-    "UPDATE iqps SET approve_status=false, is_deleted = true WHERE id=$1";
+    "UPDATE iqps SET approve_status=false, is_deleted = true, deleted_at = CURRENT_TIMESTAMP, deleted_by = $2, replaced_by = $3, version = version + 1 WHERE id=$1 AND is_deleted=false";
 
-/// Hard deletes a paper (removes it from the database)
-pub const HARD_DELETE_BY_ID: &str = "DELETE FROM iqps WHERE id=$1";
+/// Returns a query that gets the published paper ([`crate::qp::BaseQP`]) for a paper id ($1). If the paper was replaced or merged into another paper, the replacement is followed (up to 10 times).
+///
+/// Returns no rows if the paper (or its final replacement) does not exist or is not published.
+pub fn get_resolve_paper_query() -> String {
+    format!(
+        "WITH RECURSIVE chain AS (
+            SELECT id, is_deleted, COALESCE(replaced_by, merged_into) AS next_id, 0 AS depth FROM iqps WHERE id = $1
+            UNION ALL
+            SELECT iqps.id, iqps.is_deleted, COALESCE(iqps.replaced_by, iqps.merged_into), chain.depth + 1 FROM iqps JOIN chain ON iqps.id = chain.next_id WHERE chain.is_deleted AND chain.depth < 10
+        )
+        SELECT {} FROM iqps WHERE id = (SELECT id FROM chain ORDER BY depth DESC LIMIT 1) AND is_deleted = false AND approve_status = true",
+        *SEARCH_QP_FIELDS
+    )
+}
 
-/// Gets all soft-deleted papers ([`crate::db::models::DBAdminDashboardQP`]) from the database
-pub fn get_get_soft_deleted_papers_query() -> String {
+/// Returns a query that restores a soft-deleted paper ($1) to the unapproved papers with the given filelink ($2). The query also returns all the admin dashboard qp fields of the restored paper.
+pub fn get_restore_paper_query() -> String {
     format!(
-        "SELECT {} FROM iqps WHERE is_deleted=true",
-        ADMIN_DASHBOARD_QP_FIELDS
+        "UPDATE iqps SET is_deleted=false, approve_status=false, filelink=$2, deleted_at=NULL, deleted_by=NULL, reject_reason=NULL, reject_note=NULL, merged_into=NULL, replaced_by=NULL, approved_at=NULL, version=version+1 WHERE id=$1 AND is_deleted=true RETURNING {}",
+        *ADMIN_DASHBOARD_QP_FIELDS
     )
 }
 
+/// Gets the ids of the papers that have been in the trash for longer than the given number of days ($1)
+pub const GET_EXPIRED_TRASH: &str = "SELECT id FROM iqps WHERE is_deleted=true AND deleted_at < CURRENT_TIMESTAMP - make_interval(days => $1) ORDER BY attachment_of IS NULL, deleted_at";
+
+/// Rejects an unapproved paper ($1): soft-deletes it and records the admin ($2) who rejected it, the reason ($3), and a note ($4)
+pub const REJECT_BY_ID: &str = "UPDATE iqps SET approve_status=false, is_deleted=true, deleted_at=CURRENT_TIMESTAMP, deleted_by=$2, reject_reason=$3, reject_note=$4, version=version+1 WHERE id=$1 AND approve_status=false AND is_deleted=false";
+
+/// Soft-deletes a paper ($1) that was merged into another paper ($3) and records the admin ($2) who merged it
+pub const MERGE_INTO_BY_ID: &str = "UPDATE iqps SET approve_status=false, is_deleted=true, deleted_at=CURRENT_TIMESTAMP, deleted_by=$2, merged_into=$3, version=version+1 WHERE id=$1 AND is_deleted=false";
+
+/// Moves the attachments of a paper ($1) to another paper ($2), used when a paper is merged into or replaced by another paper
+pub const MOVE_ATTACHMENTS: &str =
+    "UPDATE iqps SET attachment_of=$2, version=version+1 WHERE attachment_of=$1";
+
+/// Gets the ids of the attachments of a paper ($1) that are not deleted
+pub const GET_LIVE_ATTACHMENT_IDS: &str =
+    "SELECT id FROM iqps WHERE attachment_of=$1 AND is_deleted=false ORDER BY id";
+
+/// Checks whether a paper ($1) has any attachments, including attachments in the trash
+pub const HAS_ATTACHMENTS: &str = "SELECT EXISTS (SELECT 1 FROM iqps WHERE attachment_of=$1)";
+
+/// Gets the published attachments ([`crate::qp::Attachment`]) of the papers with the given ids ($1)
+pub const GET_PUBLISHED_ATTACHMENTS: &str = "SELECT id, attachment_of, attachment_kind AS kind, filelink, note FROM iqps WHERE attachment_of = ANY($1) AND approve_status = true AND is_deleted = false ORDER BY attachment_of, attachment_kind, id";
+
+/// Copies the content hash and extracted text of a paper ($2) to another paper ($1), used when a paper's file is replaced by another paper's file
+pub const COPY_PAPER_CONTENT: &str = "UPDATE iqps SET content_hash=source.content_hash, content_text=source.content_text FROM iqps source WHERE iqps.id=$1 AND source.id=$2";
+
+/// Hard deletes a paper (removes it from the database) if it is in the trash and has no attachments
+pub const HARD_DELETE_BY_ID: &str = "DELETE FROM iqps WHERE id=$1 AND is_deleted=true AND NOT EXISTS (SELECT 1 FROM iqps attachment WHERE attachment.attachment_of=$1)";
+
+/// Gets the previous filelinks of a paper ($1) from its saved versions (e.g. old file revisions), excluding its current filelink and the filelinks used by other papers
+pub const GET_OLD_FILELINKS: &str = "SELECT DISTINCT filelink FROM iqps_history AS history WHERE paper_id=$1 AND filelink <> (SELECT filelink FROM iqps WHERE id=$1) AND NOT EXISTS (SELECT 1 FROM iqps WHERE iqps.filelink=history.filelink AND iqps.id<>$1) AND NOT EXISTS (SELECT 1 FROM iqps_history AS other WHERE other.filelink=history.filelink AND other.paper_id<>$1)";
+
 /// Get a paper ([`crate::db::models::DBAdminDashboardQP`]) with the given id (first parameter `$1`)
 pub fn get_get_paper_by_id_query() -> String {
     format!(
         "SELECT {} FROM iqps WHERE id = $1",
-        ADMIN_DASHBOARD_QP_FIELDS
+        *ADMIN_DASHBOARD_QP_FIELDS
     )
 }
 
-/// Returns a query that updates a paper's details by id ($1) (course_code, course_name, year, semester, exam, note, approve_status, filelink). `approved_by` optionally included if the edit is also used for approval.
+/// Get a paper ([`crate::db::models::DBAdminDashboardQP`]) with the given id (first parameter `$1`) and lock it until the end of the transaction
+pub fn get_lock_paper_query() -> String {
+    format!(
+        "SELECT {} FROM iqps WHERE id = $1 FOR UPDATE",
+        *ADMIN_DASHBOARD_QP_FIELDS
+    )
+}
+
+/// Returns a query that updates a paper's details by id ($1) (course_code, course_name, year, semester, exam, note, approve_status, filelink, file_revision, course_mismatch). `approved_by` optionally included if the edit is also used for approval, in which case the paper's claim is also released.
+///
+/// The paper's `version` is incremented. If `check_version` is set, the paper is only updated if its `version` matches the expected version (the last parameter).
 ///
 /// The query also returns all the admin dashboard qp fields of the edited paper
 ///
@@ -98,33 +370,137 @@ pub fn get_get_paper_by_id_query() -> String {
 /// - $7: `note`
 /// - $8: `approve_status`
 /// - $9: `filelink`
-/// - $10: `approved_by`
-pub fn get_edit_paper_query(approval: bool) -> String {
+/// - $10: `file_revision`
+/// - $11: `course_mismatch`
+/// - $12: `approved_by`
+/// - $12 or $13: expected `version`
+pub fn get_edit_paper_query(approval: bool, check_version: bool) -> String {
     format!(
-		"UPDATE iqps set course_code=$2, course_name=$3, year=$4, semester=$5, exam=$6, note=$7, approve_status=$8, filelink=$9{} WHERE id=$1 AND is_deleted=false RETURNING {}",
-		if approval {", approved_by=$10"} else {""},
-        ADMIN_DASHBOARD_QP_FIELDS
+		"UPDATE iqps set course_code=$2, course_name=$3, year=$4, semester=$5, exam=$6, note=$7, approve_status=$8, filelink=$9, file_revision=$10, course_mismatch=$11, version=version+1{} WHERE id=$1 AND is_deleted=false{} RETURNING {}",
+		if approval {", approved_by=$12, approved_at=CASE WHEN approve_status THEN approved_at ELSE CURRENT_TIMESTAMP END, claimed_by=NULL, claimed_until=NULL"} else {", approved_at=NULL"},
+		if check_version {
+			format!(" AND version=${}", if approval { 13 } else { 12 })
+		} else {
+			"".into()
+		},
+        *ADMIN_DASHBOARD_QP_FIELDS
 	)
 }
 
-/// Gets all unapproved papers ([`crate::db::models::DBAdminDashboardQP`]) from the database
-pub fn get_all_unapproved_query() -> String {
-    format!("SELECT {} FROM iqps WHERE approve_status = false and is_deleted=false ORDER BY upload_timestamp ASC", ADMIN_DASHBOARD_QP_FIELDS)
+/// Returns the `WHERE` clause for listing either the unapproved papers or the soft-deleted papers (trash). The filters are optional and can be enabled or disabled using the arguments to this function.
+///
+/// `filters` enables (in order) the `from_library`, `course_code`, `year`, `exam`, earliest `upload_timestamp` (inclusive), and latest `upload_timestamp` (inclusive) filters. Their parameters are numbered from `$1` in the same order, skipping disabled filters.
+///
+/// Returns the clause and the number of parameters used.
+fn get_paper_list_conditions(deleted: bool, filters: [bool; 6]) -> (String, usize) {
+    let mut param_num = 0;
+    let mut conditions = vec![if deleted {
+        "is_deleted=true".to_owned()
+    } else {
+        "approve_status=false AND is_deleted=false".to_owned()
+    }];
+
+    for (enabled, condition) in filters.into_iter().zip([
+        "from_library = $",
+        "UPPER(course_code) = UPPER($)",
+        "year = $",
+        "exam = $",
+        "upload_timestamp >= $",
+        "upload_timestamp <= $",
+    ]) {
+        if enabled {
+            param_num += 1;
+            conditions.push(condition.replace('$', &format!("${}", param_num)));
+        }
+    }
+
+    (format!("WHERE {}", conditions.join(" AND ")), param_num)
+}
+
+/// Returns a query that gets a page of unapproved papers or soft-deleted papers (trash) ([`crate::qp::AdminDashboardQP`]), ordered by upload time. See [`get_paper_list_conditions`] for the filters and their parameters.
+///
+/// The page size and the offset are the two parameters after the filter parameters.
+pub fn get_paper_list_query(deleted: bool, filters: [bool; 6], newest_first: bool) -> String {
+    let (conditions, param_num) = get_paper_list_conditions(deleted, filters);
+
+    format!(
+        "SELECT {} FROM iqps {} ORDER BY upload_timestamp {}, id {} LIMIT ${} OFFSET ${}",
+        *ADMIN_DASHBOARD_QP_FIELDS,
+        conditions,
+        if newest_first { "DESC" } else { "ASC" },
+        if newest_first { "DESC" } else { "ASC" },
+        param_num + 1,
+        param_num + 2
+    )
+}
+
+/// Returns a query that counts the unapproved papers or soft-deleted papers (trash) matching the filters. See [`get_paper_list_conditions`] for the filters and their parameters.
+pub fn get_paper_list_count_query(deleted: bool, filters: [bool; 6]) -> String {
+    let (conditions, _) = get_paper_list_conditions(deleted, filters);
+
+    format!("SELECT COUNT(*) FROM iqps {}", conditions)
 }
 
 /// Gets the count of unapproved papers in the database
 pub const GET_UNAPPROVED_COUNT: &str =
     "SELECT COUNT(*) FROM iqps WHERE approve_status = false AND is_deleted = false";
 
+/// Gets the uploads, approvals, and queue size ([`crate::stats::PeriodStats`]) in each period of the given length ($1, `day` or `week`) from the period containing $2 to the period containing $3.
+///
+/// The queue size at a point in time only counts papers whose approval and deletion times are known (papers approved or deleted before these were recorded are skipped).
+pub const GET_STATS_TIMELINE: &str = "WITH periods AS (
+        SELECT period_start, LEAST(period_start + ('1 ' || $1)::interval, CURRENT_TIMESTAMP::timestamp) AS period_end
+        FROM generate_series(date_trunc($1, $2::timestamp), date_trunc($1, $3::timestamp), ('1 ' || $1)::interval) AS period_start
+    )
+    SELECT period_start,
+        (SELECT COUNT(*) FROM iqps WHERE from_library = false AND upload_timestamp >= period_start AND upload_timestamp < period_end) AS uploads,
+        (SELECT COUNT(*) FROM iqps WHERE approved_at >= period_start AND approved_at < period_end) AS approvals,
+        (SELECT COUNT(*) FROM iqps WHERE upload_timestamp < period_end
+            AND (approved_at >= period_end OR (approved_at IS NULL AND approve_status = false))
+            AND (deleted_at >= period_end OR (deleted_at IS NULL AND is_deleted = false))) AS queue_size
+    FROM periods ORDER BY period_start";
+
+/// Gets the median time (in seconds) from upload to approval of the uploaded papers approved between $1 and $2
+pub const GET_MEDIAN_APPROVAL_SECONDS: &str = "SELECT percentile_cont(0.5) WITHIN GROUP (ORDER BY EXTRACT(EPOCH FROM approved_at - upload_timestamp)) FROM iqps WHERE from_library = false AND approved_at BETWEEN $1 AND $2";
+
+/// Gets the number of approved papers ([`crate::stats::CountStats`]) approved by each reviewer
+pub const GET_APPROVALS_BY_REVIEWER: &str = "SELECT approved_by AS key, COUNT(*) AS count FROM iqps WHERE approve_status = true AND approved_by <> '' GROUP BY approved_by ORDER BY count DESC, key";
+
+/// Returns a query that gets the number of published papers ([`crate::stats::CountStats`]) grouped by the given SQL expression, ordered by the given `ORDER BY` clause. Attachments are not counted.
+pub fn get_published_count_query(key: &str, order_by: &str) -> String {
+    format!(
+        "SELECT {} AS key, COUNT(*) AS count FROM iqps WHERE approve_status = true AND is_deleted = false AND attachment_of IS NULL GROUP BY key ORDER BY {}",
+        key, order_by
+    )
+}
+
+/// Returns a query that gets the number of published papers of each department ([`crate::stats::CountStats`]), by the department code (see [`crate::departments`]). Papers of unknown departments are counted by the two letter prefix of the course code.
+pub fn get_published_count_by_department_query() -> String {
+    get_published_count_query(
+        &format!(
+            "COALESCE({}, LEFT({}, 2))",
+            department_of("iqps.course_code"),
+            normalized_course_code("course_code")
+        ),
+        "count DESC, key",
+    )
+}
+
 /// Returns the query for searching question papers. It is mostly voodoo, see [blog post](https://rajivharlalka.in/posts/iqps-search-development/).
 ///
-/// The `exam_filter` argument is a vector of exam types to filter. Pass empty vector to disable the filter.
+/// The `exam_filter` argument is a vector of exam types to filter. Pass empty vector to disable the filter. Attachments are never returned, they are fetched separately for the results using [`GET_PUBLISHED_ATTACHMENTS`].
+///
+/// If `tag_filter` is set, only papers with all the given tags are returned. If `department_filter` is set, only papers of the given department are returned.
 ///
 /// Query parameters:
 /// $1 - Search query
-///
-/// Returns the query and a boolean representing whether the second argument is required
-pub fn get_qp_search_query(exam_filter: Vec<Exam>) -> String {
+/// $2 - Tag names (if `tag_filter` is set)
+/// $2 or $3 - Department code (if `department_filter` is set, numbered after the tag names)
+pub fn get_qp_search_query(
+    exam_filter: Vec<Exam>,
+    tag_filter: bool,
+    department_filter: bool,
+) -> String {
     let exam_filter_clause = exam_filter
         .iter()
         .map(|exam| {
@@ -140,12 +516,28 @@ pub fn get_qp_search_query(exam_filter: Vec<Exam>) -> String {
     let exam_clause_str = if exam_filter_clause.is_empty() {
         "".into()
     } else {
-        format!("WHERE ({} OR exam = '')", exam_filter_clause)
+        format!("AND ({} OR exam = '')", exam_filter_clause)
+    };
+
+    let tag_clause_str = if tag_filter {
+        "AND id IN (SELECT paper_id FROM paper_tags JOIN tags ON tags.id = paper_tags.tag_id WHERE tags.name = ANY($2) GROUP BY paper_id HAVING COUNT(*) = cardinality($2))"
+    } else {
+        ""
+    };
+
+    let department_clause_str = if department_filter {
+        format!(
+            "AND {} = ${}",
+            department_of("iqps.course_code"),
+            if tag_filter { 3 } else { 2 }
+        )
+    } else {
+        "".into()
     };
 
     format!("
             WITH filtered AS (
-                SELECT * from iqps {exam_filter} ORDER BY year DESC
+                SELECT * from iqps WHERE attachment_of IS NULL {exam_filter} {tag_filter} {department_filter} ORDER BY year DESC
             ),
             fuzzy AS (
                 SELECT id,
@@ -174,7 +566,7 @@ pub fn get_qp_search_query(exam_filter: Vec<Exam>) -> String {
                 LIMIT 30
             ),
             result AS (
-                SELECT {intermediate_fields}
+                SELECT filtered.id, filtered.filelink, filtered.from_library, filtered.course_code, filtered.course_name, filtered.year, filtered.semester, filtered.exam, filtered.note
                 FROM fuzzy
                     FULL OUTER JOIN full_text ON fuzzy.id = full_text.id
                     FULL OUTER JOIN partial_search ON coalesce(fuzzy.id, full_text.id) = partial_search.id
@@ -184,28 +576,313 @@ pub fn get_qp_search_query(exam_filter: Vec<Exam>) -> String {
                     coalesce(1.0 / (50 + full_text.rank_ix), 0.0) * 1 +
                     coalesce(1.0 / (50 + partial_search.rank_ix), 0.0) * 1
                 DESC
-            ) SELECT {search_qp_fields} FROM result",
-            search_qp_fields = SEARCH_QP_FIELDS,
+            ) SELECT {search_qp_fields} FROM result AS iqps",
+            search_qp_fields = *SEARCH_QP_FIELDS,
             to_tsquery = "to_tsquery('simple', websearch_to_tsquery('simple', $1)::text || ':*')",
             exam_filter = exam_clause_str,
-            intermediate_fields = ADMIN_DASHBOARD_QP_FIELDS.split(", ").map(|field| format!("filtered.{}", field)).collect::<Vec<String>>().join(", ")
+            tag_filter = tag_clause_str,
+            department_filter = department_clause_str,
         )
 }
 
 /// List of fields in the [`crate::db::models::DBAdminDashboardQP`] to be used with SELECT clauses
-pub const ADMIN_DASHBOARD_QP_FIELDS: &str = "id, filelink, from_library, course_code, course_name, year, semester, exam, note, upload_timestamp, approve_status";
+/// Expired claims are returned as `NULL`. The tags and the department are selected using subqueries on `iqps`, so the table must be named (or aliased) `iqps`.
+pub static ADMIN_DASHBOARD_QP_FIELDS: LazyLock<String> = LazyLock::new(|| {
+    format!(
+        "id, filelink, from_library, course_code, course_name, year, semester, exam, note, {} AS tags, {} AS department, upload_timestamp, approve_status, is_quarantined, COALESCE(is_deleted, false) AS is_deleted, deleted_at, deleted_by, version, file_revision, reject_reason, reject_note, merged_into, replaced_by, attachment_of, attachment_kind, course_mismatch, CASE WHEN claimed_until > CURRENT_TIMESTAMP THEN claimed_by END AS claimed_by, CASE WHEN claimed_until > CURRENT_TIMESTAMP THEN claimed_until END AS claimed_until",
+        PAPER_TAGS,
+        department_of("iqps.course_code")
+    )
+});
+
+/// List of fields in the [`crate::db::models::DBSearchQP`] to be used with SELECT clauses. See [`ADMIN_DASHBOARD_QP_FIELDS`] for the tags and the department.
+pub static SEARCH_QP_FIELDS: LazyLock<String> = LazyLock::new(|| {
+    format!(
+        "id, filelink, from_library, course_code, course_name, year, semester, exam, note, {} AS tags, {} AS department",
+        PAPER_TAGS,
+        department_of("iqps.course_code")
+    )
+});
 
-/// List of fields in the [`crate::db::models::DBSearchQP`] to be used with SELECT clauses
-pub const SEARCH_QP_FIELDS: &str =
-    "id, filelink, from_library, course_code, course_name, year, semester, exam, note";
+/// SQL subquery for the names of the tags of a paper in the `iqps` table, ordered by name
+const PAPER_TAGS: &str = "ARRAY(SELECT tags.name FROM paper_tags JOIN tags ON tags.id = paper_tags.tag_id WHERE paper_tags.paper_id = iqps.id ORDER BY tags.name)";
 
 /// Insert a newly uploaded file in the db (and return the id)
-/// Parameters in the following order: `course_code`, `course_name`, `year`, `exam`, `semester`, `note`, `filelink`, `from_library`
-pub const INSERT_NEW_QP: &str = "INSERT INTO iqps (course_code, course_name, year, exam, semester, note, filelink, from_library) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id";
+/// Parameters in the following order: `course_code`, `course_name`, `year`, `exam`, `semester`, `note`, `filelink`, `from_library`, `attachment_of`, `attachment_kind`, `course_mismatch`
+pub const INSERT_NEW_QP: &str = "INSERT INTO iqps (course_code, course_name, year, exam, semester, note, filelink, from_library, attachment_of, attachment_kind, course_mismatch) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) RETURNING id";
 
 /// Updates the filelink ($2) of a paper with the given id ($1). Used to update the filelink after a paper is uploaded.
 pub const UPDATE_FILELINK: &str = "UPDATE iqps SET filelink=$2 WHERE id=$1";
 
+/// Marks a paper ($1) as quarantined and sets its filelink ($2) to the filename in the quarantine directory
+pub const QUARANTINE_BY_ID: &str = "UPDATE iqps SET is_quarantined=true, filelink=$2 WHERE id=$1";
+
 /// Insert a library pqper in the db
 /// Parameters in the following order: `course_code`, `course_name`, `year`, `exam`, `semester`, `note`, `filelink`, `approve_status`
 pub const INSERT_NEW_LIBRARY_QP: &str = "INSERT INTO iqps (course_code, course_name, year, exam, semester, note, filelink, from_library, approve_status) VALUES ($1, $2, $3, $4, $5, $6, $7, true, $8) RETURNING id";
+
+/// Creates a new upload job and returns its id and token
+/// Parameters: `atomic`
+pub const INSERT_UPLOAD_JOB: &str =
+    "INSERT INTO upload_jobs (atomic) VALUES ($1) RETURNING id, token";
+
+/// Adds a file to an upload job
+/// Parameters in the following order: `job_id`, `filename`, `file_details`, `content_types`, `staged_files`, `status`, `message`
+pub const INSERT_UPLOAD_JOB_FILE: &str = "INSERT INTO upload_job_files (job_id, filename, file_details, content_types, staged_files, status, message) VALUES ($1, $2, $3, $4, $5, $6, $7)";
+
+/// Picks the oldest pending upload job, marks it as processing, and returns its `id` and `atomic` fields. Locked rows are skipped so that multiple workers never pick the same job.
+pub const CLAIM_NEXT_UPLOAD_JOB: &str = "UPDATE upload_jobs SET status='processing' WHERE id = (SELECT id FROM upload_jobs WHERE status='pending' ORDER BY id LIMIT 1 FOR UPDATE SKIP LOCKED) RETURNING id, atomic";
+
+/// Marks an upload job ($1) as done
+pub const FINISH_UPLOAD_JOB: &str =
+    "UPDATE upload_jobs SET status='done', finished_at=CURRENT_TIMESTAMP WHERE id=$1";
+
+/// Marks upload jobs that were being processed when the server stopped as pending again
+pub const RESET_INTERRUPTED_UPLOAD_JOBS: &str =
+    "UPDATE upload_jobs SET status='pending' WHERE status='processing'";
+
+/// Marks files of upload jobs that were being processed when the server stopped as pending again
+pub const RESET_INTERRUPTED_UPLOAD_JOB_FILES: &str =
+    "UPDATE upload_job_files SET status='pending' WHERE status='processing'";
+
+/// Gets the pending files ([`crate::routing::StagedFile`]) of an upload job ($1)
+pub const GET_PENDING_UPLOAD_JOB_FILES: &str = "SELECT id, filename, file_details, content_types, staged_files FROM upload_job_files WHERE job_id=$1 AND status='pending' ORDER BY id";
+
+/// Sets the status ($2), message ($3), and the id of the uploaded paper ($4) of an upload job file ($1)
+pub const SET_UPLOAD_JOB_FILE_STATUS: &str =
+    "UPDATE upload_job_files SET status=$2, message=$3, paper_id=$4 WHERE id=$1";
+
+/// Gets an upload job ([`crate::routing::UploadJob`]) by token ($1)
+pub const GET_UPLOAD_JOB: &str =
+    "SELECT id, token, atomic, status, created_at, finished_at FROM upload_jobs WHERE token=$1";
+
+/// Gets the statuses ([`crate::routing::UploadStatus`]) of all files in an upload job ($1)
+///
+/// Files whose paper was rejected by a reviewer have the `rejected` status and the reason as the message.
+pub const GET_UPLOAD_JOB_FILE_STATUSES: &str = "SELECT upload_job_files.filename, CASE WHEN iqps.reject_reason IS NULL THEN upload_job_files.status ELSE 'rejected' END AS status, CASE WHEN iqps.reject_reason IS NULL THEN upload_job_files.message ELSE 'Rejected by the reviewers: ' || iqps.reject_reason || COALESCE(' (' || NULLIF(iqps.reject_note, '') || ')', '') END AS message, upload_job_files.paper_id FROM upload_job_files LEFT JOIN iqps ON iqps.id = upload_job_files.paper_id AND iqps.is_deleted = true WHERE upload_job_files.job_id=$1 ORDER BY upload_job_files.id";
+
+/// Creates a new duplicate scan started by the given username ($1) and returns its id, unless a scan is already pending or being processed
+pub const INSERT_DUPLICATE_SCAN: &str = "INSERT INTO duplicate_scans (started_by) SELECT $1 WHERE NOT EXISTS (SELECT 1 FROM duplicate_scans WHERE status IN ('pending', 'processing')) RETURNING id";
+
+/// Picks the oldest pending duplicate scan, marks it as processing, and returns its id
+pub const CLAIM_NEXT_DUPLICATE_SCAN: &str = "UPDATE duplicate_scans SET status='processing' WHERE id = (SELECT id FROM duplicate_scans WHERE status='pending' ORDER BY id LIMIT 1 FOR UPDATE SKIP LOCKED) RETURNING id";
+
+/// Adds a cluster to a duplicate scan
+/// Parameters in the following order: `scan_id`, `keeper_id`, `paper_ids`, `reasons`
+pub const INSERT_DUPLICATE_CLUSTER: &str = "INSERT INTO duplicate_clusters (scan_id, keeper_id, paper_ids, reasons) VALUES ($1, $2, $3, $4)";
+
+/// Sets the status ($2) and the number of papers scanned ($3) of a duplicate scan ($1) and marks it as finished
+pub const FINISH_DUPLICATE_SCAN: &str = "UPDATE duplicate_scans SET status=$2, paper_count=$3, finished_at=CURRENT_TIMESTAMP WHERE id=$1";
+
+/// Marks duplicate scans that were being processed when the server stopped as pending again
+pub const RESET_INTERRUPTED_DUPLICATE_SCANS: &str =
+    "UPDATE duplicate_scans SET status='pending' WHERE status='processing'";
+
+/// Gets the latest duplicate scan ([`crate::duplicates::DuplicateScan`])
+pub const GET_LATEST_DUPLICATE_SCAN: &str = "SELECT id, status, started_by, paper_count, created_at, finished_at FROM duplicate_scans ORDER BY id DESC LIMIT 1";
+
+/// Gets the clusters ([`crate::duplicates::DuplicateCluster`]) found by a duplicate scan ($1)
+pub const GET_DUPLICATE_CLUSTERS: &str =
+    "SELECT keeper_id, paper_ids, reasons FROM duplicate_clusters WHERE scan_id=$1 ORDER BY id";
+
+/// Returns a query that gets the papers with the given ids ($1) that are not deleted
+pub fn get_papers_by_ids_query() -> String {
+    format!(
+        "SELECT {} FROM iqps WHERE id = ANY($1) AND is_deleted = false",
+        *ADMIN_DASHBOARD_QP_FIELDS
+    )
+}
+
+/// Returns a query that gets the papers with the given ids ($1), including deleted papers
+pub fn get_all_papers_by_ids_query() -> String {
+    format!(
+        "SELECT {} FROM iqps WHERE id = ANY($1) ORDER BY id",
+        *ADMIN_DASHBOARD_QP_FIELDS
+    )
+}
+
+/// Records an admin action in the audit log
+/// Parameters in the following order: `username`, `action`, `paper_id`, `diff`
+pub const INSERT_AUDIT_LOG: &str =
+    "INSERT INTO audit_log (username, action, paper_id, diff) VALUES ($1, $2, $3, $4)";
+
+/// Returns a query that gets entries ([`crate::audit::AuditLogEntry`]) from the audit log, latest first. The filters are optional and can be enabled or disabled using the arguments to this function.
+///
+/// Query parameters (the parameters of disabled filters are skipped):
+/// `$1` - Maximum number of entries
+/// `$2` - `paper_id`
+/// `$3` - `username`
+/// `$4` - Earliest `timestamp` (inclusive)
+/// `$5` - Latest `timestamp` (inclusive)
+pub fn get_audit_log_query(paper_id: bool, username: bool, from: bool, to: bool) -> String {
+    let mut param_num = 1;
+    let mut conditions = Vec::<String>::new();
+
+    for (enabled, condition) in [
+        (paper_id, "paper_id ="),
+        (username, "username ="),
+        (from, "timestamp >="),
+        (to, "timestamp <="),
+    ] {
+        if enabled {
+            param_num += 1;
+            conditions.push(format!("{} ${}", condition, param_num));
+        }
+    }
+
+    format!(
+        "SELECT id, username, action, paper_id, timestamp, diff FROM audit_log {} ORDER BY timestamp DESC, id DESC LIMIT $1",
+        if conditions.is_empty() {
+            "".into()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        }
+    )
+}
+
+/// List of fields in [`crate::qp::PaperVersion`] to be used with SELECT clauses
+pub const PAPER_VERSION_FIELDS: &str = "id, paper_id, course_code, course_name, year, semester, exam, note, approve_status, filelink, file_revision, edited_by, edited_at";
+
+/// Saves the current details of a paper ($1) as its original version, if no versions of the paper are saved yet. Papers uploaded before the history was kept have no versions.
+pub const INSERT_ORIGINAL_PAPER_VERSION: &str = "INSERT INTO iqps_history (paper_id, course_code, course_name, year, semester, exam, note, approve_status, filelink, file_revision, edited_by, edited_at) SELECT id, course_code, course_name, year, semester, exam, note, COALESCE(approve_status, false), filelink, file_revision, NULL, COALESCE(upload_timestamp, CURRENT_TIMESTAMP) FROM iqps WHERE id=$1 AND NOT EXISTS (SELECT 1 FROM iqps_history WHERE paper_id=$1)";
+
+/// Saves the current details of a paper ($1) as a new version, edited by the given username ($2)
+pub const INSERT_PAPER_VERSION: &str = "INSERT INTO iqps_history (paper_id, course_code, course_name, year, semester, exam, note, approve_status, filelink, file_revision, edited_by) SELECT id, course_code, course_name, year, semester, exam, note, COALESCE(approve_status, false), filelink, file_revision, $2 FROM iqps WHERE id=$1";
+
+/// Gets all the versions ([`crate::qp::PaperVersion`]) of a paper ($1), latest first
+pub fn get_paper_history_query() -> String {
+    format!(
+        "SELECT {} FROM iqps_history WHERE paper_id=$1 ORDER BY id DESC",
+        PAPER_VERSION_FIELDS
+    )
+}
+
+/// Gets a version ([`crate::qp::PaperVersion`]) of a paper by its id ($1)
+pub fn get_paper_version_query() -> String {
+    format!(
+        "SELECT {} FROM iqps_history WHERE id=$1",
+        PAPER_VERSION_FIELDS
+    )
+}
+
+/// Assigns a role ($2) to an admin ($1) if they do not have one yet, and returns the admin's role
+pub const GET_OR_ASSIGN_ROLE: &str = "WITH inserted AS (INSERT INTO admin_roles (username, role) VALUES ($1, $2) ON CONFLICT (username) DO NOTHING RETURNING role) SELECT role FROM inserted UNION ALL SELECT role FROM admin_roles WHERE username=$1";
+
+/// Gets the role of an admin ($1)
+pub const GET_ADMIN_ROLE: &str = "SELECT role FROM admin_roles WHERE username=$1";
+
+/// Gets the roles ([`crate::auth::AdminRole`]) of all admins
+pub const GET_ADMIN_ROLES: &str =
+    "SELECT username, role, assigned_by, updated_at FROM admin_roles ORDER BY username";
+
+/// Sets the role ($2) of an admin ($1), assigned by another admin ($3)
+pub const SET_ADMIN_ROLE: &str = "INSERT INTO admin_roles (username, role, assigned_by) VALUES ($1, $2, $3) ON CONFLICT (username) DO UPDATE SET role=EXCLUDED.role, assigned_by=EXCLUDED.assigned_by, updated_at=CURRENT_TIMESTAMP";
+
+/// Returns a query that claims a paper ($1) for an admin ($2) for a number of minutes ($3). The claim only succeeds if the paper is not claimed by another admin (or the claim has expired). Claiming a paper again extends the claim.
+///
+/// The query returns all the admin dashboard qp fields of the claimed paper, and no rows if the claim failed.
+pub fn get_claim_paper_query() -> String {
+    format!(
+        "UPDATE iqps SET claimed_by=$2, claimed_until=CURRENT_TIMESTAMP + make_interval(mins => $3) WHERE id=$1 AND is_deleted=false AND (claimed_by IS NULL OR claimed_by=$2 OR claimed_until <= CURRENT_TIMESTAMP) RETURNING {}",
+        *ADMIN_DASHBOARD_QP_FIELDS
+    )
+}
+
+/// Releases the claim on a paper ($1) held by an admin ($2)
+pub const UNCLAIM_PAPER: &str =
+    "UPDATE iqps SET claimed_by=NULL, claimed_until=NULL WHERE id=$1 AND claimed_by=$2";
+
+/// Returns a query that gets the tags ([`crate::tags::Tag`]) along with the number of published papers with each tag, ordered by name. If `by_id` is set, only the tag with the given id ($1) is returned.
+pub fn get_tags_query(by_id: bool) -> String {
+    format!(
+        "SELECT tags.id, tags.name, tags.description, tags.created_by, tags.created_at, COUNT(iqps.id) AS paper_count
+        FROM tags
+            LEFT JOIN paper_tags ON paper_tags.tag_id = tags.id
+            LEFT JOIN iqps ON iqps.id = paper_tags.paper_id AND iqps.approve_status = true AND iqps.is_deleted = false
+        {} GROUP BY tags.id ORDER BY tags.name",
+        if by_id { "WHERE tags.id = $1" } else { "" }
+    )
+}
+
+/// Gets the tag names in $1 that do not exist
+pub const GET_UNKNOWN_TAGS: &str =
+    "SELECT DISTINCT name FROM unnest($1::text[]) AS name WHERE name NOT IN (SELECT name FROM tags)";
+
+/// Creates a tag with a name ($1), description ($2), and the admin ($3) who created it. Returns the id of the tag, or no rows if a tag with the name exists.
+pub const INSERT_TAG: &str = "INSERT INTO tags (name, description, created_by) VALUES ($1, $2, $3) ON CONFLICT (name) DO NOTHING RETURNING id";
+
+/// Updates the name ($2) and description ($3) of a tag ($1)
+pub const UPDATE_TAG: &str = "UPDATE tags SET name=$2, description=$3 WHERE id=$1";
+
+/// Deletes a tag ($1), removing it from all papers
+pub const DELETE_TAG: &str = "DELETE FROM tags WHERE id=$1";
+
+/// Gets the ids of the papers with a tag ($1)
+pub const GET_TAGGED_PAPER_IDS: &str = "SELECT paper_id FROM paper_tags WHERE tag_id=$1";
+
+/// Adds the tags with the given names ($2) to a paper ($1)
+pub const ADD_PAPER_TAGS: &str = "INSERT INTO paper_tags (paper_id, tag_id) SELECT $1, id FROM tags WHERE name = ANY($2) ON CONFLICT DO NOTHING";
+
+/// Removes the tags with the given names ($2) from a paper ($1)
+pub const REMOVE_PAPER_TAGS: &str = "DELETE FROM paper_tags USING tags WHERE paper_tags.tag_id = tags.id AND paper_tags.paper_id = $1 AND tags.name = ANY($2)";
+
+/// Returns a query that increments the version of the papers with the given ids ($1), used when the tags of the papers change without an edit. Returns all the admin dashboard qp fields of the papers.
+pub fn get_touch_papers_query() -> String {
+    format!(
+        "UPDATE iqps SET version=version+1 WHERE id = ANY($1) RETURNING {}",
+        *ADMIN_DASHBOARD_QP_FIELDS
+    )
+}
+
+/// List of fields in the [`crate::courses::Course`] to be used with SELECT clauses
+const COURSE_FIELDS: &str = "code, name, department, credits, first_year, last_year";
+
+/// Returns a query that gets all the courses in the registry ([`crate::courses::Course`]), ordered by code. If `department_filter` is set, only the courses of the given department ($1) are returned (see [`crate::departments`]).
+pub fn get_courses_query(department_filter: bool) -> String {
+    format!(
+        "SELECT {} FROM courses {} ORDER BY code",
+        COURSE_FIELDS,
+        if department_filter {
+            format!("WHERE {} = $1", department_of("courses.code"))
+        } else {
+            "".into()
+        }
+    )
+}
+
+/// Returns a query that gets the course with the given (normalized) code ($1)
+pub fn get_course_query() -> String {
+    format!("SELECT {} FROM courses WHERE code = $1", COURSE_FIELDS)
+}
+
+/// Adds a course to the registry, or updates it if a course with the code exists
+/// Parameters in the following order: `code`, `name`, `department`, `credits`, `first_year`, `last_year`
+pub const UPSERT_COURSE: &str = "INSERT INTO courses (code, name, department, credits, first_year, last_year) VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT (code) DO UPDATE SET name=EXCLUDED.name, department=EXCLUDED.department, credits=EXCLUDED.credits, first_year=EXCLUDED.first_year, last_year=EXCLUDED.last_year";
+
+/// Removes the course with the given code ($1) from the registry
+pub const DELETE_COURSE: &str = "DELETE FROM courses WHERE code = $1";
+
+/// Returns a query that gets all the departments ([`crate::departments::Department`]) along with the number of published papers and registered courses of each department, ordered by name
+pub fn get_departments_query() -> String {
+    format!(
+        "WITH published AS (
+            SELECT {} AS department FROM iqps WHERE approve_status = true AND is_deleted = false AND attachment_of IS NULL
+        ),
+        registered AS (
+            SELECT {} AS department FROM courses
+        )
+        SELECT departments.code, departments.name, departments.prefixes,
+            (SELECT COUNT(*) FROM published WHERE published.department = departments.code) AS paper_count,
+            (SELECT COUNT(*) FROM registered WHERE registered.department = departments.code) AS course_count
+        FROM departments ORDER BY departments.name",
+        department_of("iqps.course_code"),
+        department_of("courses.code")
+    )
+}
+
+/// Adds a department, or updates it if a department with the code exists
+/// Parameters in the following order: `code`, `name`, `prefixes`
+pub const UPSERT_DEPARTMENT: &str = "INSERT INTO departments (code, name, prefixes) VALUES ($1, $2, $3) ON CONFLICT (code) DO UPDATE SET name=EXCLUDED.name, prefixes=EXCLUDED.prefixes";
+
+/// Removes the department with the given code ($1)
+pub const DELETE_DEPARTMENT: &str = "DELETE FROM departments WHERE code = $1";
@@ -0,0 +1,75 @@
+//! Departments, used to browse papers and courses before knowing the exact course.
+//!
+//! A paper's department is not stored. If its course is in the registry (see [`crate::courses`]) with a department matching the code or name of a department, that department is used. Otherwise, it is inferred from the prefix of its course code (e.g. `CS` in `CS10003`). Each department has a list of course code prefixes, which can be configured by the admins. If the prefixes of multiple departments match a course code, the longest prefix wins.
+
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use crate::courses;
+
+#[derive(Serialize, sqlx::FromRow)]
+/// A department along with the number of its published papers and registered courses
+pub struct Department {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub details: DepartmentDetails,
+    /// The number of published papers of the department
+    pub paper_count: i64,
+    /// The number of courses of the department in the course registry (see [`crate::courses`])
+    pub course_count: i64,
+}
+
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+/// The configurable details of a department
+pub struct DepartmentDetails {
+    /// A short code identifying the department (e.g. `CS`), stored in uppercase. Used as the department of papers and in the `department` filters.
+    pub code: String,
+    /// The full name of the department
+    pub name: String,
+    /// The course code prefixes of the department's courses, normalized like course codes (see [`courses::normalize_course_code`])
+    pub prefixes: Vec<String>,
+}
+
+impl DepartmentDetails {
+    /// Normalizes the department's code and prefixes, and trims its name. Returns an error if a field is empty or a prefix is not alphanumeric.
+    pub fn normalize(self) -> Result<Self, color_eyre::eyre::Error> {
+        let mut prefixes = self
+            .prefixes
+            .iter()
+            .map(|prefix| courses::normalize_course_code(prefix))
+            .filter(|prefix| !prefix.is_empty())
+            .collect::<Vec<String>>();
+        prefixes.sort();
+        prefixes.dedup();
+
+        let department = Self {
+            code: normalize_department_code(&self.code),
+            name: self.name.trim().to_owned(),
+            prefixes,
+        };
+
+        if department.code.is_empty() {
+            Err(eyre!("The department code cannot be empty."))
+        } else if department.name.is_empty() {
+            Err(eyre!("The department name cannot be empty."))
+        } else if department.prefixes.is_empty() {
+            Err(eyre!("The department must have at least one prefix."))
+        } else if let Some(prefix) = department
+            .prefixes
+            .iter()
+            .find(|prefix| !prefix.chars().all(char::is_alphanumeric))
+        {
+            Err(eyre!(
+                "The prefix `{}` must only contain letters and digits.",
+                prefix
+            ))
+        } else {
+            Ok(department)
+        }
+    }
+}
+
+/// Normalizes a department code: converts it to uppercase and trims it. Departments are always stored and matched by their normalized codes.
+pub fn normalize_department_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
@@ -0,0 +1,559 @@
+//! Utils for finding duplicate question papers.
+//!
+//! Candidate duplicates of a paper are fetched from the database along with a set of [`SimilarityScores`], which are combined into a single score and a list of human readable reasons.
+//!
+//! The whole archive is also scanned for duplicates in the background, grouping the approved and library papers into [`DuplicateCluster`]s (see [`find_clusters`]).
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::Serialize;
+
+use crate::qp::AdminDashboardQP;
+
+/// Candidates with a lower score are not considered duplicates
+pub const MIN_CANDIDATE_SCORE: f32 = 0.3;
+
+/// Minimum trigram similarity of the course names (or contents) for it to count towards the score
+const MIN_TEXT_SIMILARITY: f32 = 0.3;
+
+#[derive(sqlx::FromRow)]
+/// How similar a candidate paper is to the original paper
+pub struct SimilarityScores {
+    /// Whether the course codes match, ignoring case and whitespace
+    pub course_code_match: bool,
+    /// Trigram similarity of the course names (0 to 1)
+    pub name_similarity: f32,
+    /// Difference between the years of the papers
+    pub year_difference: i32,
+    pub same_semester: bool,
+    pub same_exam: bool,
+    /// Whether the files have the same hash
+    pub same_content: bool,
+    /// Trigram similarity of the text extracted from the files (0 if either has no text)
+    pub text_similarity: f32,
+}
+
+impl SimilarityScores {
+    /// Combines the scores into a single score between 0 and 1, along with the reasons that contributed to it.
+    pub fn score(&self) -> (f32, Vec<String>) {
+        let mut score = 0.0;
+        let mut reasons = Vec::<String>::new();
+
+        if self.same_content {
+            score += 1.0;
+            reasons.push("Identical file contents".into());
+        }
+
+        if self.course_code_match {
+            score += 0.35;
+            reasons.push("Same course code".into());
+        }
+
+        if self.name_similarity >= MIN_TEXT_SIMILARITY {
+            score += 0.25 * self.name_similarity;
+            reasons.push(format!(
+                "Course name is {:.0}% similar",
+                self.name_similarity * 100.0
+            ));
+        }
+
+        match self.year_difference {
+            0 => {
+                score += 0.15;
+                reasons.push("Same year".into());
+            }
+            1 => {
+                score += 0.05;
+                reasons.push("Neighbouring year".into());
+            }
+            _ => {}
+        }
+
+        if self.same_semester && self.same_exam {
+            score += 0.1;
+            reasons.push("Same semester and exam".into());
+        } else if self.same_exam {
+            score += 0.05;
+            reasons.push("Same exam".into());
+        }
+
+        if self.text_similarity >= MIN_TEXT_SIMILARITY {
+            score += 0.4 * self.text_similarity;
+            reasons.push(format!(
+                "Contents are {:.0}% similar",
+                self.text_similarity * 100.0
+            ));
+        }
+
+        (score.min(1.0), reasons)
+    }
+}
+
+#[derive(sqlx::FromRow)]
+/// A candidate duplicate as fetched from the database
+pub struct CandidateRow {
+    #[sqlx(flatten)]
+    pub paper: AdminDashboardQP,
+    #[sqlx(flatten)]
+    pub scores: SimilarityScores,
+}
+
+#[derive(Serialize)]
+/// A paper that may be a duplicate of another paper
+pub struct SimilarCandidate {
+    #[serde(flatten)]
+    pub paper: AdminDashboardQP,
+    /// How likely the paper is a duplicate (0 to 1)
+    pub score: f32,
+    /// Why the paper matched
+    pub reasons: Vec<String>,
+}
+
+impl From<CandidateRow> for SimilarCandidate {
+    fn from(value: CandidateRow) -> Self {
+        let (score, reasons) = value.scores.score();
+
+        Self {
+            paper: value.paper,
+            score,
+            reasons,
+        }
+    }
+}
+
+/// Scores the candidate duplicates and returns the ones scoring at least [`MIN_CANDIDATE_SCORE`], best first.
+pub fn rank_candidates(candidates: Vec<CandidateRow>, limit: usize) -> Vec<SimilarCandidate> {
+    let mut candidates = candidates
+        .into_iter()
+        .map(SimilarCandidate::from)
+        .filter(|candidate| candidate.score >= MIN_CANDIDATE_SCORE)
+        .collect::<Vec<SimilarCandidate>>();
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates.truncate(limit);
+
+    candidates
+}
+
+/// Minimum trigram similarity of the extracted texts of two papers with the same course code for them to be clustered together
+pub const NEAR_IDENTICAL_TEXT_SIMILARITY: f32 = 0.9;
+
+#[derive(sqlx::FromRow)]
+/// A paper considered by a duplicate scan
+pub struct ScanPaper {
+    pub id: i32,
+    pub from_library: bool,
+    /// The course code in uppercase and without whitespace
+    pub course_code: String,
+    pub year: i32,
+    pub semester: String,
+    pub exam: String,
+    /// Hash of the file contents
+    pub content_hash: Option<String>,
+    /// Hash of the extracted text, ignoring case and whitespace (`None` if there is no text)
+    pub text_hash: Option<String>,
+    /// Number of characters of extracted text
+    pub text_length: i32,
+}
+
+impl ScanPaper {
+    /// The course code, year, semester, and exam of the paper. `None` if any of them is not known, as papers with unknown details can not be matched.
+    fn metadata_key(&self) -> Option<(&str, i32, &str, &str)> {
+        if self.course_code.is_empty()
+            || self.semester.is_empty()
+            || self.exam.is_empty()
+            // A class test without a number could be any class test
+            || self.exam == "ct"
+        {
+            None
+        } else {
+            Some((&self.course_code, self.year, &self.semester, &self.exam))
+        }
+    }
+}
+
+#[derive(sqlx::FromRow, Serialize)]
+/// A group of papers that are likely duplicates of each other
+pub struct DuplicateCluster {
+    /// The id of the paper suggested to be kept
+    pub keeper_id: i32,
+    pub paper_ids: Vec<i32>,
+    /// Why the papers were grouped together
+    pub reasons: Vec<String>,
+}
+
+#[derive(sqlx::FromRow, Serialize)]
+/// A scan of the archive for duplicates
+pub struct DuplicateScan {
+    pub id: i32,
+    /// The state of the scan: `pending`, `processing`, `done`, or `failed`
+    pub status: String,
+    /// The username of the admin who started the scan
+    pub started_by: String,
+    /// The number of papers scanned
+    pub paper_count: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub finished_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Finds the root of the set containing the given index in a union-find structure, compressing the path along the way
+fn find_root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+
+    index
+}
+
+/// Groups papers into clusters of likely duplicates. Papers are grouped if they have the same file contents, the same extracted text, the same course code, year, semester, and exam, or if they are one of the given pairs of papers with near-identical text.
+///
+/// The suggested keeper of each cluster is a library paper if there is one (as they are from the official source), then the paper with the most extracted text, then the oldest paper.
+pub fn find_clusters(
+    papers: &[ScanPaper],
+    near_identical_pairs: &[(i32, i32)],
+) -> Vec<DuplicateCluster> {
+    let indices = papers
+        .iter()
+        .enumerate()
+        .map(|(index, paper)| (paper.id, index))
+        .collect::<HashMap<i32, usize>>();
+
+    // Pairs of papers that matched, along with the reason
+    let mut matches = Vec::<(usize, usize, &str)>::new();
+
+    let mut content_hashes = HashMap::<&str, usize>::new();
+    let mut text_hashes = HashMap::<&str, usize>::new();
+    let mut metadata = HashMap::<(&str, i32, &str, &str), usize>::new();
+    for (index, paper) in papers.iter().enumerate() {
+        if let Some(hash) = &paper.content_hash {
+            if let Some(&first) = content_hashes.get(hash.as_str()) {
+                matches.push((first, index, "Identical file contents"));
+            } else {
+                content_hashes.insert(hash, index);
+            }
+        }
+
+        if let Some(hash) = &paper.text_hash {
+            if let Some(&first) = text_hashes.get(hash.as_str()) {
+                matches.push((first, index, "Identical text"));
+            } else {
+                text_hashes.insert(hash, index);
+            }
+        }
+
+        if let Some(key) = paper.metadata_key() {
+            if let Some(&first) = metadata.get(&key) {
+                matches.push((first, index, "Same course code, year, semester, and exam"));
+            } else {
+                metadata.insert(key, index);
+            }
+        }
+    }
+
+    for (a, b) in near_identical_pairs {
+        if let (Some(&a), Some(&b)) = (indices.get(a), indices.get(b)) {
+            matches.push((a, b, "Near-identical text"));
+        }
+    }
+
+    // Union-find over the indices of the papers
+    let mut parents = (0..papers.len()).collect::<Vec<usize>>();
+    for &(a, b, _) in &matches {
+        let (root_a, root_b) = (find_root(&mut parents, a), find_root(&mut parents, b));
+        if root_a != root_b {
+            parents[root_b] = root_a;
+        }
+    }
+
+    let mut reasons = HashMap::<usize, BTreeSet<&str>>::new();
+    for &(a, _, reason) in &matches {
+        let root = find_root(&mut parents, a);
+        reasons.entry(root).or_default().insert(reason);
+    }
+
+    let mut members = HashMap::<usize, Vec<&ScanPaper>>::new();
+    for (index, paper) in papers.iter().enumerate() {
+        let root = find_root(&mut parents, index);
+        if reasons.contains_key(&root) {
+            members.entry(root).or_default().push(paper);
+        }
+    }
+
+    let mut clusters = members
+        .into_iter()
+        .filter_map(|(root, papers)| {
+            let keeper = papers.iter().max_by_key(|paper| {
+                (
+                    paper.from_library,
+                    paper.text_length,
+                    std::cmp::Reverse(paper.id),
+                )
+            })?;
+
+            let mut paper_ids = papers.iter().map(|paper| paper.id).collect::<Vec<i32>>();
+            paper_ids.sort();
+
+            Some(DuplicateCluster {
+                keeper_id: keeper.id,
+                paper_ids,
+                reasons: reasons[&root]
+                    .iter()
+                    .map(|reason| reason.to_string())
+                    .collect(),
+            })
+        })
+        .collect::<Vec<DuplicateCluster>>();
+
+    clusters.sort_by_key(|cluster| cluster.paper_ids[0]);
+
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scores of a paper that has nothing in common with the original paper
+    fn unrelated() -> SimilarityScores {
+        SimilarityScores {
+            course_code_match: false,
+            name_similarity: 0.0,
+            year_difference: 5,
+            same_semester: false,
+            same_exam: false,
+            same_content: false,
+            text_similarity: 0.0,
+        }
+    }
+
+    fn assert_score(scores: SimilarityScores, expected_score: f32, expected_reasons: &[&str]) {
+        let (score, reasons) = scores.score();
+
+        assert!(
+            (score - expected_score).abs() < 1e-6,
+            "expected a score of {expected_score}, got {score}"
+        );
+        assert_eq!(reasons, expected_reasons);
+    }
+
+    #[test]
+    fn unrelated_papers_score_zero() {
+        assert_score(unrelated(), 0.0, &[]);
+    }
+
+    #[test]
+    fn matching_details_add_up() {
+        assert_score(
+            SimilarityScores {
+                course_code_match: true,
+                year_difference: 0,
+                same_semester: true,
+                same_exam: true,
+                ..unrelated()
+            },
+            0.6,
+            &["Same course code", "Same year", "Same semester and exam"],
+        );
+
+        assert_score(
+            SimilarityScores {
+                year_difference: 1,
+                same_exam: true,
+                ..unrelated()
+            },
+            0.1,
+            &["Neighbouring year", "Same exam"],
+        );
+    }
+
+    #[test]
+    fn similarities_count_above_the_threshold() {
+        assert_score(
+            SimilarityScores {
+                name_similarity: 0.8,
+                text_similarity: 0.5,
+                ..unrelated()
+            },
+            0.4,
+            &["Course name is 80% similar", "Contents are 50% similar"],
+        );
+
+        assert_score(
+            SimilarityScores {
+                name_similarity: 0.29,
+                text_similarity: 0.29,
+                ..unrelated()
+            },
+            0.0,
+            &[],
+        );
+    }
+
+    #[test]
+    fn score_is_capped_at_one() {
+        assert_score(
+            SimilarityScores {
+                course_code_match: true,
+                name_similarity: 1.0,
+                year_difference: 0,
+                same_semester: true,
+                same_exam: true,
+                same_content: true,
+                text_similarity: 1.0,
+            },
+            1.0,
+            &[
+                "Identical file contents",
+                "Same course code",
+                "Course name is 100% similar",
+                "Same year",
+                "Same semester and exam",
+                "Contents are 100% similar",
+            ],
+        );
+    }
+
+    /// A paper with unknown details, which is only matched by its contents
+    fn scan_paper(id: i32) -> ScanPaper {
+        ScanPaper {
+            id,
+            from_library: false,
+            course_code: "".into(),
+            year: 2024,
+            semester: "".into(),
+            exam: "".into(),
+            content_hash: None,
+            text_hash: None,
+            text_length: 0,
+        }
+    }
+
+    /// A paper with the given course code, year, semester, and exam
+    fn scan_paper_with_details(id: i32, course_code: &str, exam: &str) -> ScanPaper {
+        ScanPaper {
+            course_code: course_code.into(),
+            semester: "autumn".into(),
+            exam: exam.into(),
+            ..scan_paper(id)
+        }
+    }
+
+    #[test]
+    fn unmatched_papers_are_not_clustered() {
+        let papers = [
+            scan_paper_with_details(1, "CS10001", "midsem"),
+            scan_paper_with_details(2, "CS10001", "endsem"),
+            scan_paper(3),
+        ];
+
+        assert!(find_clusters(&papers, &[]).is_empty());
+    }
+
+    #[test]
+    fn matches_are_clustered_transitively() {
+        let papers = [
+            ScanPaper {
+                content_hash: Some("file".into()),
+                ..scan_paper_with_details(1, "CS10001", "midsem")
+            },
+            scan_paper_with_details(2, "CS10001", "midsem"),
+            ScanPaper {
+                content_hash: Some("file".into()),
+                text_hash: Some("text".into()),
+                ..scan_paper(3)
+            },
+            ScanPaper {
+                text_hash: Some("text".into()),
+                ..scan_paper(4)
+            },
+            scan_paper(5),
+        ];
+
+        let clusters = find_clusters(&papers, &[]);
+
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].paper_ids, vec![1, 2, 3, 4]);
+        assert_eq!(
+            clusters[0].reasons,
+            vec![
+                "Identical file contents",
+                "Identical text",
+                "Same course code, year, semester, and exam",
+            ]
+        );
+    }
+
+    #[test]
+    fn near_identical_pairs_are_clustered() {
+        let papers = [scan_paper(1), scan_paper(2), scan_paper(3), scan_paper(4)];
+
+        // Pairs with papers that were not scanned are ignored
+        let clusters = find_clusters(&papers, &[(4, 2), (3, 10)]);
+
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].paper_ids, vec![2, 4]);
+        assert_eq!(clusters[0].reasons, vec!["Near-identical text"]);
+    }
+
+    #[test]
+    fn unknown_details_and_unnumbered_class_tests_do_not_match() {
+        let papers = [
+            scan_paper_with_details(1, "CS10001", "ct"),
+            scan_paper_with_details(2, "CS10001", "ct"),
+            scan_paper_with_details(3, "", "midsem"),
+            scan_paper_with_details(4, "", "midsem"),
+            scan_paper_with_details(5, "CS10001", "ct1"),
+            scan_paper_with_details(6, "CS10001", "ct1"),
+        ];
+
+        let clusters = find_clusters(&papers, &[]);
+
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].paper_ids, vec![5, 6]);
+    }
+
+    #[test]
+    fn keeper_prefers_library_papers_then_more_text_then_older_papers() {
+        let duplicate = |id: i32, from_library: bool, text_length: i32| ScanPaper {
+            from_library,
+            text_length,
+            ..scan_paper_with_details(id, "CS10001", "midsem")
+        };
+
+        let keeper_id = |papers: &[ScanPaper]| find_clusters(papers, &[])[0].keeper_id;
+
+        assert_eq!(
+            keeper_id(&[duplicate(1, false, 500), duplicate(2, true, 10)]),
+            2
+        );
+        assert_eq!(
+            keeper_id(&[duplicate(1, false, 10), duplicate(2, false, 500)]),
+            2
+        );
+        assert_eq!(
+            keeper_id(&[duplicate(2, false, 10), duplicate(1, false, 10)]),
+            1
+        );
+    }
+
+    #[test]
+    fn clusters_are_sorted_by_their_first_paper() {
+        let papers = [
+            scan_paper_with_details(5, "MA10001", "endsem"),
+            scan_paper_with_details(2, "CS10001", "midsem"),
+            scan_paper_with_details(6, "MA10001", "endsem"),
+            scan_paper_with_details(3, "CS10001", "midsem"),
+        ];
+
+        let clusters = find_clusters(&papers, &[]);
+
+        assert_eq!(
+            clusters
+                .iter()
+                .map(|cluster| cluster.paper_ids.clone())
+                .collect::<Vec<Vec<i32>>>(),
+            vec![vec![2, 3], vec![5, 6]]
+        );
+    }
+}
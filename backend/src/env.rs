@@ -43,6 +43,14 @@ pub struct EnvVars {
     pub max_upload_limit: usize,
     /// Location where logs are stored
     pub log_location: PathBuf,
+    /// Number of days a soft-deleted paper is kept in the trash before it is permanently deleted (purging is disabled if 0)
+    pub trash_retention_days: i32,
+
+    // Malware scanning
+    /// Path to the clamd unix socket used to scan uploads (scanning is disabled if empty)
+    pub clamd_socket_path: String,
+    /// Whether uploads are accepted when the scanner is unavailable (fail-open) or rejected (fail-closed)
+    pub scanner_fail_open: bool,
 
     // Paths
     /// The URL of the static files server (odin's vault)
@@ -53,6 +61,10 @@ pub struct EnvVars {
     uploaded_qps_path: PathBuf,
     /// The path where library papers (scrapped) are stored, relative to the `static_file_storage_location`
     library_qps_path: PathBuf,
+    /// The path where infected uploads are quarantined. This must NOT be inside the `static_file_storage_location`
+    quarantine_location: PathBuf,
+    /// The path where uploaded files are staged until they are processed. This should NOT be inside the `static_file_storage_location`
+    upload_staging_location: PathBuf,
 
     // Server
     /// The port the server listens on
@@ -87,6 +99,13 @@ impl EnvVars {
         let log_location = std::env::var("LOG_LOCATION")
             .unwrap_or("./log/application.log".to_string())
             .into();
+        let trash_retention_days: i32 = std::env::var("TRASH_RETENTION_DAYS")
+            .map(|s| s.parse())
+            .unwrap_or(Ok(30))?;
+        let clamd_socket_path = std::env::var("CLAMD_SOCKET_PATH").unwrap_or_default();
+        let scanner_fail_open: bool = std::env::var("SCANNER_FAIL_OPEN")
+            .map(|s| s.parse())
+            .unwrap_or(Ok(false))?;
         let static_files_url =
             std::env::var("STATIC_FILES_URL").unwrap_or("https://static.metakgp.org".to_string());
         let static_file_storage_location = std::env::var("STATIC_FILE_STORAGE_LOCATION")
@@ -98,6 +117,12 @@ impl EnvVars {
         let library_qps_path = std::env::var("LIBRARY_QPS_PATH")
             .unwrap_or("/peqp/qp".to_string())
             .into();
+        let quarantine_location = std::env::var("QUARANTINE_LOCATION")
+            .unwrap_or("./quarantine".to_string())
+            .into();
+        let upload_staging_location = std::env::var("UPLOAD_STAGING_LOCATION")
+            .unwrap_or("./staging".to_string())
+            .into();
         let server_port: i32 = std::env::var("SERVER_PORT")
             .map(|s| s.parse())
             .unwrap_or(Ok(8080))?;
@@ -119,10 +144,15 @@ impl EnvVars {
             slack_webhook_url,
             max_upload_limit,
             log_location,
+            trash_retention_days,
+            clamd_socket_path,
+            scanner_fail_open,
             static_files_url,
             static_file_storage_location,
             uploaded_qps_path,
             library_qps_path,
+            quarantine_location,
+            upload_staging_location,
             server_port,
             cors_allowed_origins,
             paths: Paths::default(),
@@ -136,6 +166,8 @@ impl EnvVars {
             &self.static_file_storage_location,
             &self.uploaded_qps_path,
             &self.library_qps_path,
+            &self.quarantine_location,
+            &self.upload_staging_location,
         )?;
         self.log_location = std::path::absolute(self.log_location)?;
 
@@ -1,7 +1,15 @@
+pub mod audit;
 pub mod auth;
+pub mod courses;
 pub mod db;
+pub mod departments;
+pub mod duplicates;
 pub mod env;
 pub mod pathutils;
+pub mod pdf;
 pub mod qp;
 pub mod routing;
+pub mod scanner;
 pub mod slack;
+pub mod stats;
+pub mod tags;
@@ -17,10 +17,12 @@ pub enum PaperCategory {
     Approved,
     /// Library paper (scraped using the peqp scraper)
     Library,
+    /// Approved attachment of a paper (eg: solutions)
+    Attachment,
 }
 
 #[derive(Default)]
-/// A set of paths (absolute, relative, or even URLs) for all three categories of papers (directories)
+/// A set of paths (absolute, relative, or even URLs) for all the categories of papers (directories)
 struct PathTriad {
     /// Unapproved paper path
     pub unapproved: PathBuf,
@@ -28,6 +30,8 @@ struct PathTriad {
     pub approved: PathBuf,
     /// Library paper path
     pub library: PathBuf,
+    /// Approved attachment path
+    pub attachment: PathBuf,
 }
 
 impl PathTriad {
@@ -37,6 +41,7 @@ impl PathTriad {
             PaperCategory::Approved => self.approved.to_owned(),
             PaperCategory::Unapproved => self.unapproved.to_owned(),
             PaperCategory::Library => self.library.to_owned(),
+            PaperCategory::Attachment => self.attachment.to_owned(),
         }
     }
 }
@@ -48,10 +53,16 @@ pub struct Paths {
     /// The absolute path to the location from where the static files server serves files
     static_files_path: PathBuf,
 
-    /// The slugs to all three directories
+    /// The slugs to all the directories
     ///
     /// A slug is a relative path independent of the URL or system path. This slug is stored in the database and either the [`crate::pathutils::Paths::static_files_url`] or the [`crate::pathutils::Paths::static_files_path`] is prepended to it to get its URL (to send to the frontend) or the system path (for backend operations)
     path_slugs: PathTriad,
+
+    /// The absolute path to the directory where infected uploads are quarantined. This is not served publicly, so quarantined papers have no slug or URL.
+    quarantine_path: PathBuf,
+
+    /// The absolute path to the directory where uploaded files are staged until the upload worker processes them. Not served publicly.
+    staging_path: PathBuf,
 }
 
 impl Default for Paths {
@@ -61,6 +72,8 @@ impl Default for Paths {
                 .expect("This library thinks https://metakgp.org is not a valid URL."),
             static_files_path: PathBuf::default(),
             path_slugs: PathTriad::default(),
+            quarantine_path: PathBuf::default(),
+            staging_path: PathBuf::default(),
         }
     }
 }
@@ -73,18 +86,23 @@ impl Paths {
     /// * `static_file_storage_location` - The path to the location on the server from which the static files are served (eg: /srv/static)
     /// * `uploaded_qps_relative_path` - The path to the uploaded question papers, relative to the static files storage location. (eg: /iqps/uploaded)
     /// * `library_qps_relative_path` - The path to the library question papers, relative to the static files storage location. (eg: /peqp/qp)
+    /// * `quarantine_location` - The path to the directory where infected uploads are quarantined. (eg: /srv/quarantine)
+    /// * `upload_staging_location` - The path to the directory where uploads are staged before processing. (eg: /srv/staging)
     pub fn new(
         static_files_url: &str,
         static_file_storage_location: &Path,
         uploaded_qps_relative_path: &Path,
         library_qps_relative_path: &Path,
+        quarantine_location: &Path,
+        upload_staging_location: &Path,
     ) -> Result<Self, color_eyre::eyre::Error> {
         // The slugs for each of the uploaded papers directories
         let path_slugs = PathTriad {
-            // Use subdirectories `/unapproved` and `/approved` inside the uploaded qps path
+            // Use subdirectories `/unapproved`, `/approved`, and `/attachments` inside the uploaded qps path
             unapproved: uploaded_qps_relative_path.join("unapproved"),
             approved: uploaded_qps_relative_path.join("approved"),
             library: library_qps_relative_path.to_owned(),
+            attachment: uploaded_qps_relative_path.join("attachments"),
         };
 
         // The absolute system paths for each of the directories
@@ -92,6 +110,7 @@ impl Paths {
             unapproved: path::absolute(static_file_storage_location.join(&path_slugs.unapproved))?,
             approved: path::absolute(static_file_storage_location.join(&path_slugs.approved))?,
             library: path::absolute(static_file_storage_location.join(&path_slugs.library))?,
+            attachment: path::absolute(static_file_storage_location.join(&path_slugs.attachment))?,
         };
 
         // Ensure these system paths exist
@@ -111,18 +130,35 @@ impl Paths {
             ));
         }
 
-        // Create dirs for unapproved and approved
+        // Create dirs for unapproved, approved, and attachments
         if !system_paths.unapproved.exists() {
             fs::create_dir(&system_paths.unapproved)?;
         }
         if !system_paths.approved.exists() {
             fs::create_dir(&system_paths.approved)?;
         }
+        if !system_paths.attachment.exists() {
+            fs::create_dir(&system_paths.attachment)?;
+        }
+
+        // Create the quarantine dir
+        let quarantine_path = path::absolute(quarantine_location)?;
+        if !quarantine_path.exists() {
+            fs::create_dir_all(&quarantine_path)?;
+        }
+
+        // Create the staging dir
+        let staging_path = path::absolute(upload_staging_location)?;
+        if !staging_path.exists() {
+            fs::create_dir_all(&staging_path)?;
+        }
 
         Ok(Self {
             static_files_url: Url::parse(static_files_url)?,
             static_files_path: path::absolute(static_file_storage_location)?,
             path_slugs,
+            quarantine_path,
+            staging_path,
         })
     }
 
@@ -135,11 +171,32 @@ impl Paths {
             .to_string()
     }
 
+    /// Returns the slug of an unapproved uploaded paper's file. Papers whose pages have been edited carry their file revision in the filename (eg: `12_r2.pdf`).
+    pub fn get_unapproved_slug(&self, id: i32, file_revision: i32) -> String {
+        let filename = if file_revision > 0 {
+            format!("{}_r{}.pdf", id, file_revision)
+        } else {
+            format!("{}.pdf", id)
+        };
+
+        self.get_slug(&filename, PaperCategory::Unapproved)
+    }
+
     /// Returns the absolute system path from a given slug
     pub fn get_path_from_slug(&self, slug: &str) -> PathBuf {
         self.static_files_path.join(slug)
     }
 
+    /// Returns the absolute system path of a quarantined file with the given filename
+    pub fn get_quarantine_path(&self, filename: &str) -> PathBuf {
+        self.quarantine_path.join(filename)
+    }
+
+    /// Returns the absolute system path of a staged upload with the given filename
+    pub fn get_staging_path(&self, filename: &str) -> PathBuf {
+        self.staging_path.join(filename)
+    }
+
     /// Returns the static server URL for a given slug
     pub fn get_url_from_slug(&self, slug: &str) -> Result<String, color_eyre::eyre::Error> {
         Ok(self.static_files_url.join(slug)?.as_str().to_string())
@@ -162,3 +219,21 @@ impl Paths {
             .join("-") // Join the parts with `-`
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unapproved_slug_has_no_suffix_for_the_original_file() {
+        let paths = Paths::default();
+        assert_eq!(paths.get_unapproved_slug(12, 0), "12.pdf");
+    }
+
+    #[test]
+    fn unapproved_slug_keeps_the_revision_after_a_page_edit() {
+        // A paper restored after its pages were edited must point to the rewritten file, not the original
+        let paths = Paths::default();
+        assert_eq!(paths.get_unapproved_slug(12, 2), "12_r2.pdf");
+    }
+}
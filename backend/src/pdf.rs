@@ -0,0 +1,398 @@
+//! Utils for rewriting question paper PDFs.
+//!
+//! Any rewrite of a PDF (merging files, rotating, deleting, or reordering pages) can be expressed as a list of [`PageSpec`]s, and [`assemble`] builds a new PDF containing exactly those pages.
+
+use color_eyre::eyre::{eyre, Result};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+/// Page attributes that a page can inherit from its ancestors in the page tree. These are copied onto the page since the page tree is rebuilt.
+const INHERITABLE_PAGE_KEYS: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// Maximum depth of the page tree walked when collecting inherited attributes
+const MAX_PAGE_TREE_DEPTH: usize = 64;
+
+/// A page to be included in an assembled PDF
+pub struct PageSpec {
+    /// The index of the source document
+    pub document: usize,
+    /// The page number in the source document (starting from 1)
+    pub page: u32,
+    /// The clockwise rotation (in degrees, a multiple of 90) added to the page's current rotation
+    pub rotation: i64,
+}
+
+/// Parses a PDF from its bytes
+pub fn load(data: &[u8]) -> Result<Document> {
+    Ok(Document::load_mem(data)?)
+}
+
+/// Returns the number of pages in a PDF
+pub fn page_count(document: &Document) -> u32 {
+    document.get_pages().len() as u32
+}
+
+/// Extracts the text of a PDF, lowercased with whitespace collapsed, truncated to the given number of characters. Pages whose text can not be extracted (eg: scanned pages) are skipped.
+pub fn extract_text(document: &Document, max_chars: usize) -> String {
+    let mut text = String::new();
+
+    for page in 1..=page_count(document) {
+        if let Ok(page_text) = document.extract_text(&[page]) {
+            text.push_str(&page_text);
+            text.push(' ');
+        }
+
+        if text.len() >= max_chars * 2 {
+            break;
+        }
+    }
+
+    text.split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+        .chars()
+        .take(max_chars)
+        .collect()
+}
+
+/// Merges multiple PDFs into one, keeping the pages in order.
+pub fn merge(documents: Vec<Document>) -> Result<Vec<u8>> {
+    let pages = documents
+        .iter()
+        .enumerate()
+        .flat_map(|(index, document)| {
+            (1..=page_count(document)).map(move |page| PageSpec {
+                document: index,
+                page,
+                rotation: 0,
+            })
+        })
+        .collect::<Vec<PageSpec>>();
+
+    assemble(documents, &pages)
+}
+
+/// Builds a new PDF with the given pages (in the given order) from the source documents. A page can be used more than once.
+///
+/// Returns the bytes of the new PDF.
+pub fn assemble(documents: Vec<Document>, pages: &[PageSpec]) -> Result<Vec<u8>> {
+    if pages.is_empty() {
+        return Err(eyre!("A PDF must have at least one page."));
+    }
+
+    let mut assembled = Document::with_version("1.5");
+    let mut max_id = 1;
+
+    // The (flattened) page dictionaries of each source document, by page number
+    let mut source_pages = Vec::<Vec<(ObjectId, Dictionary)>>::new();
+
+    for mut document in documents {
+        // Renumber so that the object ids of the documents do not clash
+        document.renumber_objects_with(max_id);
+        max_id = document.max_id + 1;
+
+        let document_pages = document
+            .get_pages()
+            .into_values()
+            .map(|page_id| Ok((page_id, get_page_dictionary(&document, page_id)?)))
+            .collect::<Result<Vec<(ObjectId, Dictionary)>>>()?;
+        source_pages.push(document_pages);
+
+        // The page tree and the document structure are rebuilt, everything else (contents, fonts, images) is kept
+        for (object_id, object) in document.objects {
+            match object.type_name().unwrap_or("") {
+                "Catalog" | "Pages" | "Page" | "Outlines" | "Outline" => {}
+                _ => {
+                    assembled.objects.insert(object_id, object);
+                }
+            }
+        }
+    }
+
+    let pages_id = (max_id, 0);
+    max_id += 1;
+
+    let mut kids = Vec::<Object>::new();
+    let mut used_page_ids = Vec::<ObjectId>::new();
+    for spec in pages {
+        let (page_id, page) = spec
+            .page
+            .checked_sub(1)
+            .and_then(|index| source_pages.get(spec.document)?.get(index as usize))
+            .ok_or(eyre!(
+                "Page {} of document {} does not exist.",
+                spec.page,
+                spec.document
+            ))?;
+
+        let mut page = page.clone();
+        page.set("Parent", pages_id);
+
+        if spec.rotation % 90 != 0 {
+            return Err(eyre!(
+                "Pages can only be rotated by multiples of 90 degrees."
+            ));
+        } else if spec.rotation != 0 {
+            let current_rotation = page.get(b"Rotate").and_then(Object::as_i64).unwrap_or(0);
+            page.set("Rotate", (current_rotation + spec.rotation).rem_euclid(360));
+        }
+
+        // A page used more than once needs a new object id
+        let page_id = if used_page_ids.contains(page_id) {
+            max_id += 1;
+            (max_id - 1, 0)
+        } else {
+            used_page_ids.push(*page_id);
+            *page_id
+        };
+
+        assembled.objects.insert(page_id, Object::Dictionary(page));
+        kids.push(Object::Reference(page_id));
+    }
+
+    let mut pages_dict = Dictionary::new();
+    pages_dict.set("Type", Object::Name(b"Pages".to_vec()));
+    pages_dict.set("Count", kids.len() as i64);
+    pages_dict.set("Kids", kids);
+    assembled
+        .objects
+        .insert(pages_id, Object::Dictionary(pages_dict));
+
+    let catalog_id = (max_id, 0);
+    max_id += 1;
+
+    let mut catalog = Dictionary::new();
+    catalog.set("Type", Object::Name(b"Catalog".to_vec()));
+    catalog.set("Pages", pages_id);
+    assembled
+        .objects
+        .insert(catalog_id, Object::Dictionary(catalog));
+
+    assembled.trailer.set("Root", catalog_id);
+    assembled.max_id = max_id;
+
+    // Drop the objects only used by pages that were left out
+    assembled.prune_objects();
+    assembled.renumber_objects();
+    assembled.compress();
+
+    let mut output = Vec::new();
+    assembled.save_to(&mut output)?;
+
+    Ok(output)
+}
+
+/// Returns a page's dictionary along with the attributes it inherits from the page tree
+fn get_page_dictionary(document: &Document, page_id: ObjectId) -> Result<Dictionary> {
+    let mut page = document.get_dictionary(page_id)?.clone();
+
+    let mut parent_id = page.get(b"Parent").and_then(Object::as_reference).ok();
+    for _ in 0..MAX_PAGE_TREE_DEPTH {
+        let Some(id) = parent_id else {
+            break;
+        };
+
+        let parent = document.get_dictionary(id)?;
+        for key in INHERITABLE_PAGE_KEYS {
+            if !page.has(key) {
+                if let Ok(value) = parent.get(key) {
+                    page.set(key, value.clone());
+                }
+            }
+        }
+
+        parent_id = parent.get(b"Parent").and_then(Object::as_reference).ok();
+    }
+
+    Ok(page)
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::{
+        content::{Content, Operation},
+        dictionary, Stream,
+    };
+
+    use super::*;
+
+    /// Builds a PDF with one page per text. The resources, the media box, and the rotation (if any) are set on the page tree so that the pages inherit them.
+    fn document(texts: &[&str], rotation: Option<i64>) -> Document {
+        let mut document = Document::with_version("1.5");
+        let pages_id = document.new_object_id();
+
+        let font_id = document.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+
+        let mut kids = Vec::<Object>::new();
+        for text in texts {
+            let content = Content {
+                operations: vec![
+                    Operation::new("BT", vec![]),
+                    Operation::new("Tf", vec!["F1".into(), 24.into()]),
+                    Operation::new("Td", vec![100.into(), 600.into()]),
+                    Operation::new("Tj", vec![Object::string_literal(*text)]),
+                    Operation::new("ET", vec![]),
+                ],
+            };
+            let content_id =
+                document.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+            let page_id = document.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+            });
+            kids.push(page_id.into());
+        }
+
+        let mut pages = dictionary! {
+            "Type" => "Pages",
+            "Count" => kids.len() as i64,
+            "Kids" => kids,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        };
+        if let Some(rotation) = rotation {
+            pages.set("Rotate", rotation);
+        }
+        document.objects.insert(pages_id, Object::Dictionary(pages));
+
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        document.trailer.set("Root", catalog_id);
+
+        // Round trip through bytes like an uploaded file
+        let mut data = Vec::new();
+        document.save_to(&mut data).unwrap();
+        load(&data).unwrap()
+    }
+
+    /// Returns the text and the rotation of each page of a PDF
+    fn pages(data: &[u8]) -> Vec<(String, i64)> {
+        let document = load(data).unwrap();
+
+        document
+            .get_pages()
+            .into_iter()
+            .map(|(page, page_id)| {
+                let text = document.extract_text(&[page]).unwrap();
+                let rotation = get_page_dictionary(&document, page_id)
+                    .unwrap()
+                    .get(b"Rotate")
+                    .and_then(Object::as_i64)
+                    .unwrap_or(0);
+
+                (text.trim().to_owned(), rotation)
+            })
+            .collect()
+    }
+
+    fn spec(document: usize, page: u32, rotation: i64) -> PageSpec {
+        PageSpec {
+            document,
+            page,
+            rotation,
+        }
+    }
+
+    #[test]
+    fn merge_keeps_the_pages_in_order() {
+        let merged = merge(vec![
+            document(&["one", "two"], None),
+            document(&["three"], None),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            pages(&merged),
+            vec![("one".into(), 0), ("two".into(), 0), ("three".into(), 0)]
+        );
+    }
+
+    #[test]
+    fn assemble_reorders_deletes_and_repeats_pages() {
+        let assembled = assemble(
+            vec![document(&["one", "two", "three"], None)],
+            &[spec(0, 3, 0), spec(0, 1, 0), spec(0, 3, 0)],
+        )
+        .unwrap();
+
+        assert_eq!(
+            pages(&assembled),
+            vec![("three".into(), 0), ("one".into(), 0), ("three".into(), 0)]
+        );
+    }
+
+    #[test]
+    fn rotation_is_added_to_the_inherited_rotation() {
+        let assembled = assemble(
+            vec![document(&["one", "two", "three"], Some(90))],
+            &[spec(0, 1, 0), spec(0, 2, 90), spec(0, 3, -180)],
+        )
+        .unwrap();
+
+        assert_eq!(
+            pages(&assembled),
+            vec![
+                ("one".into(), 90),
+                ("two".into(), 180),
+                ("three".into(), 270)
+            ]
+        );
+    }
+
+    #[test]
+    fn rotation_wraps_around() {
+        let assembled = assemble(
+            vec![document(&["one", "two"], None)],
+            &[spec(0, 1, -90), spec(0, 2, 450)],
+        )
+        .unwrap();
+
+        assert_eq!(
+            pages(&assembled),
+            vec![("one".into(), 270), ("two".into(), 90)]
+        );
+    }
+
+    #[test]
+    fn inherited_attributes_are_copied_to_the_pages() {
+        let assembled = assemble(vec![document(&["one"], None)], &[spec(0, 1, 0)]).unwrap();
+        let assembled = load(&assembled).unwrap();
+        let page_id = assembled.get_pages()[&1];
+        let page = assembled.get_dictionary(page_id).unwrap();
+
+        assert!(page.has(b"MediaBox"));
+        assert!(page.has(b"Resources"));
+    }
+
+    #[test]
+    fn invalid_page_specs_are_rejected() {
+        let invalid_specs = [
+            vec![],
+            vec![spec(0, 0, 0)],
+            vec![spec(0, 3, 0)],
+            vec![spec(1, 1, 0)],
+            vec![spec(0, 1, 45)],
+        ];
+
+        for specs in invalid_specs {
+            assert!(assemble(vec![document(&["one", "two"], None)], &specs).is_err());
+        }
+    }
+
+    #[test]
+    fn extracted_text_is_normalized_and_truncated() {
+        // Merged so that the pages have their own resources
+        let merged = merge(vec![document(&["Hello   World", "SECOND page"], None)]).unwrap();
+        let document = load(&merged).unwrap();
+
+        assert_eq!(extract_text(&document, 100), "hello world second page");
+        assert_eq!(extract_text(&document, 8), "hello wo");
+    }
+}
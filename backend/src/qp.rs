@@ -1,5 +1,7 @@
 //! Utils for parsing question paper details
 
+use std::path::PathBuf;
+
 use color_eyre::eyre::eyre;
 use duplicate::duplicate_item;
 use serde::Deserialize;
@@ -103,10 +105,89 @@ impl From<&Exam> for String {
     }
 }
 
+/// The reason a reviewer rejected an uploaded paper.
+///
+/// Can be converted to and parsed from a String using the [`From`] and [`TryFrom`] trait implementations.
+pub enum RejectReason {
+    /// The paper is already on the website, parsed from `duplicate`
+    Duplicate,
+    /// The file does not match the details it was uploaded with, parsed from `wrong_paper`
+    WrongPaper,
+    /// The file can not be read, parsed from `illegible`
+    Illegible,
+    /// The file is not a question paper, parsed from `not_a_question_paper`
+    NotAQuestionPaper,
+    /// Any other reason (explained in the note), parsed from `other`
+    Other,
+}
+
+impl TryFrom<&str> for RejectReason {
+    type Error = color_eyre::eyre::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "duplicate" => Ok(RejectReason::Duplicate),
+            "wrong_paper" => Ok(RejectReason::WrongPaper),
+            "illegible" => Ok(RejectReason::Illegible),
+            "not_a_question_paper" => Ok(RejectReason::NotAQuestionPaper),
+            "other" => Ok(RejectReason::Other),
+            _ => Err(eyre!("Error parsing reject reason: Invalid value.")),
+        }
+    }
+}
+
+impl From<&RejectReason> for String {
+    fn from(value: &RejectReason) -> Self {
+        match value {
+            RejectReason::Duplicate => "duplicate".into(),
+            RejectReason::WrongPaper => "wrong_paper".into(),
+            RejectReason::Illegible => "illegible".into(),
+            RejectReason::NotAQuestionPaper => "not_a_question_paper".into(),
+            RejectReason::Other => "other".into(),
+        }
+    }
+}
+
+/// The kind of an attachment, a file related to a question paper that is not a question paper itself (see [`Attachment`]).
+///
+/// Can be converted to and parsed from a String using the [`From`] and [`TryFrom`] trait implementations.
+pub enum AttachmentKind {
+    /// Solutions to the paper, parsed from `solution`
+    Solution,
+    /// Answer key (only the final answers), parsed from `answer_key`
+    AnswerKey,
+    /// Marking scheme used for evaluation, parsed from `marking_scheme`
+    MarkingScheme,
+}
+
+impl TryFrom<&str> for AttachmentKind {
+    type Error = color_eyre::eyre::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "solution" => Ok(AttachmentKind::Solution),
+            "answer_key" => Ok(AttachmentKind::AnswerKey),
+            "marking_scheme" => Ok(AttachmentKind::MarkingScheme),
+            _ => Err(eyre!("Error parsing attachment kind: Invalid value.")),
+        }
+    }
+}
+
+impl From<&AttachmentKind> for String {
+    fn from(value: &AttachmentKind) -> Self {
+        match value {
+            AttachmentKind::Solution => "solution".into(),
+            AttachmentKind::AnswerKey => "answer_key".into(),
+            AttachmentKind::MarkingScheme => "marking_scheme".into(),
+        }
+    }
+}
+
 #[duplicate_item(
     Serializable;
     [ Exam ];
     [ Semester ];
+    [ AttachmentKind ];
 )]
 impl Serialize for Serializable {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -146,8 +227,31 @@ pub struct BaseQP {
     pub semester: Semester,
     pub exam: Exam,
     pub note: String,
+    /// The names of the paper's tags (see [`crate::tags`])
+    pub tags: Vec<String>,
+    /// The code of the paper's department, inferred from the course code. `None` if the department is not known (see [`crate::departments`]).
+    pub department: Option<String>,
 }
 
+#[derive(Serialize, sqlx::FromRow)]
+/// A published file attached to a question paper, such as its solutions
+pub struct Attachment {
+    pub id: i32,
+    /// The id of the paper this file is attached to
+    pub attachment_of: i32,
+    /// The kind of attachment (see [`AttachmentKind`])
+    pub kind: String,
+    pub filelink: String,
+    pub note: String,
+}
+
+#[derive(Serialize)]
+/// A question paper sent from the search endpoint, along with its published attachments
+pub struct SearchQP {
+    #[serde(flatten)]
+    pub qp: BaseQP,
+    pub attachments: Vec<Attachment>,
+}
 
 #[derive(Serialize, sqlx::FromRow)]
 /// The fields of a question paper sent from the admin dashboard endpoints.
@@ -161,6 +265,76 @@ pub struct AdminDashboardQP {
     pub qp: BaseQP,
     pub upload_timestamp: chrono::NaiveDateTime,
     pub approve_status: bool,
+    /// Whether the paper failed the malware scan. Quarantined papers are never published.
+    pub is_quarantined: bool,
+    /// Whether the paper is soft-deleted (in the trash)
+    pub is_deleted: bool,
+    /// When the paper was soft-deleted. `None` if the paper is not deleted (or was deleted before deletions were recorded).
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    /// The username of the admin who soft-deleted the paper
+    pub deleted_by: Option<String>,
+    /// Incremented every time the paper is changed. Used to reject edits made on an outdated version of the paper.
+    pub version: i32,
+    /// Incremented every time the paper's file is rewritten (eg: when its pages are edited). Each revision is stored in a separate file.
+    pub file_revision: i32,
+    /// Why the paper was rejected by a reviewer (see [`RejectReason`]). `None` if the paper was not rejected.
+    pub reject_reason: Option<String>,
+    /// The reviewer's explanation of the rejection
+    pub reject_note: Option<String>,
+    /// The id of the paper this paper was merged into, if it was deleted by a merge
+    pub merged_into: Option<i32>,
+    /// The id of the paper that replaced this paper during an edit, if it was deleted by the edit
+    pub replaced_by: Option<i32>,
+    /// The id of the paper this file is attached to. `None` if this is a question paper and not an attachment.
+    pub attachment_of: Option<i32>,
+    /// The kind of attachment (see [`AttachmentKind`]). `None` if this is not an attachment.
+    pub attachment_kind: Option<String>,
+    /// Why the paper's course details do not match the course registry (see [`crate::courses::check_course`]). `None` if they match.
+    pub course_mismatch: Option<String>,
+    /// The username of the admin reviewing the paper. `None` if the paper is not claimed or the claim has expired.
+    pub claimed_by: Option<String>,
+    /// When the claim expires
+    pub claimed_until: Option<chrono::NaiveDateTime>,
+}
+
+impl AdminDashboardQP {
+    /// Returns whether the paper is claimed by an admin other than the given one
+    pub fn is_claimed_by_other(&self, username: &str) -> bool {
+        self.claimed_by
+            .as_ref()
+            .is_some_and(|claimed_by| claimed_by != username)
+    }
+
+    /// Returns the absolute system path of the paper's file. Quarantined papers are stored outside the static files directory.
+    pub fn get_system_path(&self, env_vars: &EnvVars) -> PathBuf {
+        if self.is_quarantined {
+            env_vars.paths.get_quarantine_path(&self.qp.filelink)
+        } else {
+            env_vars.paths.get_path_from_slug(&self.qp.filelink)
+        }
+    }
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+/// A saved version of a question paper's details. A new version is saved every time a paper is edited.
+pub struct PaperVersion {
+    /// The id of the version
+    pub id: i32,
+    pub paper_id: i32,
+    pub course_code: String,
+    pub course_name: String,
+    pub year: i32,
+    pub semester: Semester,
+    pub exam: Exam,
+    pub note: String,
+    pub approve_status: bool,
+    /// The slug of the paper's file in this version
+    pub filelink: String,
+    /// The revision of the paper's file in this version
+    pub file_revision: i32,
+    /// The username of the admin who made this version. `None` for the original version of the paper.
+    pub edited_by: Option<String>,
+    pub edited_at: chrono::NaiveDateTime,
 }
 
 impl WithUrl for BaseQP {
@@ -172,8 +346,28 @@ impl WithUrl for BaseQP {
     }
 }
 
+impl WithUrl for Attachment {
+    fn with_url(self, env_vars: &EnvVars) -> Result<Self, color_eyre::eyre::Error> {
+        Ok(Self {
+            filelink: env_vars.paths.get_url_from_slug(&self.filelink)?,
+            ..self
+        })
+    }
+}
+
 impl WithUrl for AdminDashboardQP {
     fn with_url(self, env_vars: &EnvVars) -> Result<Self, color_eyre::eyre::Error> {
+        // Quarantined files are not served, do not leak a link to them
+        if self.is_quarantined {
+            return Ok(Self {
+                qp: BaseQP {
+                    filelink: "".into(),
+                    ..self.qp
+                },
+                ..self
+            });
+        }
+
         Ok(Self {
             qp: self.qp.with_url(env_vars)?,
             ..self
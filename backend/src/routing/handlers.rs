@@ -8,26 +8,39 @@ use axum::{
     body::Bytes,
     extract::{Json, Multipart},
     http::StatusCode,
+    response::{IntoResponse, Redirect, Response},
     Extension,
 };
+use chrono::{NaiveDate, NaiveDateTime};
 use color_eyre::eyre::{eyre, ContextCompat, Result};
 use http::HeaderMap;
 use serde::Serialize;
+use sqlx::{Postgres, Transaction};
 use tokio::fs;
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::PathBuf,
+};
 
-use axum::extract::{Query, State};
+use axum::extract::{Path, Query, State};
 use serde::Deserialize;
 
 use crate::{
-    auth::{self, Auth},
+    audit::{self, AuditAction, AuditLogEntry},
+    auth::{self, AdminRole, Auth, Role},
+    courses::{self, Course},
+    departments::{self, Department, DepartmentDetails},
+    duplicates::{self, DuplicateScan, SimilarCandidate},
     pathutils::PaperCategory,
-    qp::{self, AdminDashboardQP, Exam, WithUrl},
-    slack::send_slack_message,
+    pdf,
+    qp::{self, AdminDashboardQP, AttachmentKind, Exam, PaperVersion, RejectReason, WithUrl},
+    stats::{Stats, StatsPeriod},
+    tags::{self, Tag},
 };
 
-use super::{AppError, BackendResponse, HandlerState};
+use super::{workers, AppError, BackendResponse, HandlerState, RouterState};
 
 /// The return type of a handler function. T is the data type returned if the operation was a success
 type HandlerReturn<T> = Result<(StatusCode, BackendResponse<T>), AppError>;
@@ -37,24 +50,117 @@ pub async fn healthcheck() -> HandlerReturn<()> {
     Ok(BackendResponse::ok("Hello, World.".into(), ()))
 }
 
-/// Fetches all the unapproved papers.
-pub async fn get_unapproved(State(state): HandlerState) -> HandlerReturn<Vec<AdminDashboardQP>> {
-    let papers = state.db.get_unapproved_papers().await?;
+/// Default number of papers in a page of the unapproved papers or the trash
+const DEFAULT_PAGE_SIZE: i64 = 100;
+/// Maximum number of papers in a page of the unapproved papers or the trash
+const MAX_PAGE_SIZE: i64 = 500;
+
+/// The request format for the unapproved papers and trash endpoints, parsed from the query parameters. All parameters are optional.
+pub struct PaperListReq {
+    /// The page number, starting from 1 (`page`)
+    pub page: i64,
+    /// The number of papers in a page (`per_page`)
+    pub per_page: i64,
+    /// Only show library papers if true, uploaded papers if false (`from_library`)
+    pub from_library: Option<bool>,
+    /// Only show papers of this course, case insensitive (`course_code`)
+    pub course_code: Option<String>,
+    /// Only show papers of this year (`year`)
+    pub year: Option<i32>,
+    /// Only show papers of this exam (`exam`)
+    pub exam: Option<Exam>,
+    /// Only show papers uploaded on or after this time (`uploaded_from`, `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS`)
+    pub uploaded_from: Option<NaiveDateTime>,
+    /// Only show papers uploaded on or before this time (`uploaded_to`, `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS`)
+    pub uploaded_to: Option<NaiveDateTime>,
+    /// Sort by upload time, newest first if `sort=newest` and oldest first (default) if `sort=oldest`
+    pub newest_first: bool,
+}
 
-    let papers = papers
-        .into_iter()
-        .map(|paper| paper.with_url(&state.env_vars))
-        .collect::<Result<Vec<qp::AdminDashboardQP>, color_eyre::eyre::Error>>()?;
+impl TryFrom<&HashMap<String, String>> for PaperListReq {
+    type Error = String;
+
+    fn try_from(params: &HashMap<String, String>) -> Result<Self, Self::Error> {
+        fn parse<T: std::str::FromStr>(
+            params: &HashMap<String, String>,
+            key: &str,
+        ) -> Result<Option<T>, String> {
+            params
+                .get(key)
+                .map(|value| value.parse::<T>())
+                .transpose()
+                .map_err(|_| format!("Invalid `{}`.", key))
+        }
 
-    Ok(BackendResponse::ok(
-        format!("Successfully fetched {} papers.", papers.len()),
-        papers,
-    ))
+        let page = parse::<i64>(params, "page")?.unwrap_or(1);
+        let per_page = parse::<i64>(params, "per_page")?.unwrap_or(DEFAULT_PAGE_SIZE);
+        if page < 1 || !(1..=MAX_PAGE_SIZE).contains(&per_page) {
+            return Err(format!(
+                "`page` must be at least 1 and `per_page` must be between 1 and {}.",
+                MAX_PAGE_SIZE
+            ));
+        }
+
+        let mut uploaded_timestamps = [None, None];
+        for (timestamp, (key, end_of_day)) in uploaded_timestamps
+            .iter_mut()
+            .zip([("uploaded_from", false), ("uploaded_to", true)])
+        {
+            if let Some(value) = params.get(key) {
+                *timestamp =
+                    Some(parse_timestamp(value, end_of_day).ok_or(format!("Invalid `{}`.", key))?);
+            }
+        }
+        let [uploaded_from, uploaded_to] = uploaded_timestamps;
+
+        Ok(Self {
+            page,
+            per_page,
+            from_library: parse::<bool>(params, "from_library")?,
+            course_code: params.get("course_code").cloned(),
+            year: parse::<i32>(params, "year")?,
+            exam: params
+                .get("exam")
+                .map(|exam| Exam::try_from(exam.as_str()))
+                .transpose()
+                .map_err(|_| "Invalid `exam`.".to_owned())?,
+            uploaded_from,
+            uploaded_to,
+            newest_first: match params.get("sort").map(|sort| sort.as_str()) {
+                None | Some("oldest") => false,
+                Some("newest") => true,
+                Some(_) => return Err("`sort` must be `oldest` or `newest`.".into()),
+            },
+        })
+    }
 }
 
-/// Gets all papers which have been soft-deleted.
-pub async fn get_trash(State(state): HandlerState) -> HandlerReturn<Vec<AdminDashboardQP>> {
-    let papers = state.db.get_soft_deleted_papers().await?;
+#[derive(Serialize)]
+/// The response format for the unapproved papers and trash endpoints
+pub struct PaperListRes {
+    papers: Vec<AdminDashboardQP>,
+    /// The current page number
+    page: i64,
+    /// The number of papers in a page
+    per_page: i64,
+    /// The number of papers matching the filters
+    total: i64,
+    /// The number of papers in the list (unapproved papers or trash), ignoring the filters
+    total_unfiltered: i64,
+}
+
+/// Fetches a page of the unapproved or soft-deleted papers matching the filters in the query parameters.
+async fn get_paper_list(
+    state: &RouterState,
+    deleted: bool,
+    params: &HashMap<String, String>,
+) -> HandlerReturn<PaperListRes> {
+    let req = match PaperListReq::try_from(params) {
+        Ok(req) => req,
+        Err(message) => return Ok(BackendResponse::error(message, StatusCode::BAD_REQUEST)),
+    };
+
+    let (papers, total, total_unfiltered) = state.db.get_paper_list(deleted, &req).await?;
 
     let papers = papers
         .into_iter()
@@ -63,10 +169,36 @@ pub async fn get_trash(State(state): HandlerState) -> HandlerReturn<Vec<AdminDas
 
     Ok(BackendResponse::ok(
         format!("Successfully fetched {} papers.", papers.len()),
-        papers,
+        PaperListRes {
+            papers,
+            page: req.page,
+            per_page: req.per_page,
+            total,
+            total_unfiltered,
+        },
     ))
 }
 
+/// Fetches a page of the unapproved papers, oldest first by default.
+///
+/// Request format - [`PaperListReq`]
+pub async fn get_unapproved(
+    State(state): HandlerState,
+    Query(params): Query<HashMap<String, String>>,
+) -> HandlerReturn<PaperListRes> {
+    get_paper_list(&state, false, &params).await
+}
+
+/// Fetches a page of the papers which have been soft-deleted, oldest first by default.
+///
+/// Request format - [`PaperListReq`]
+pub async fn get_trash(
+    State(state): HandlerState,
+    Query(params): Query<HashMap<String, String>>,
+) -> HandlerReturn<PaperListRes> {
+    get_paper_list(&state, true, &params).await
+}
+
 /// Fetches a paper by id.
 pub async fn get_paper_details(
     State(state): HandlerState,
@@ -94,15 +226,34 @@ pub async fn get_paper_details(
     }
 }
 
-/// Searches for question papers given a query and an optional `exam` parameter.
+/// Redirects to the file of a published paper. Links to papers that were replaced or merged into another paper redirect to the replacement, so that old shared links keep working.
+///
+/// # Request Path Parameters
+/// * `id`: The id of the paper.
+pub async fn paper(State(state): HandlerState, Path(id): Path<i32>) -> Result<Response, AppError> {
+    if let Some(paper) = state.db.resolve_paper(id).await? {
+        let paper = paper.with_url(&state.env_vars)?;
+
+        Ok(Redirect::temporary(&paper.filelink).into_response())
+    } else {
+        Ok(
+            BackendResponse::<()>::error("Error: Paper not found.".into(), StatusCode::NOT_FOUND)
+                .into_response(),
+        )
+    }
+}
+
+/// Searches for question papers given a query and an optional `exam` parameter. Each paper is returned along with its published attachments (eg: solutions).
 ///
 /// # Request Query Parameters
 /// * `query`: The query string to search in the question papers (searches course name or code)
 /// * `exam` (optional): A comma-separated string of exam types to filter. Leave empty to match any exam.
+/// * `tags` (optional): A comma-separated string of tag names. Only papers with all the given tags are returned.
+/// * `department` (optional): A department code (see [`departments`]). Only papers of the department are returned.
 pub async fn search(
     State(state): HandlerState,
     Query(params): Query<HashMap<String, String>>,
-) -> HandlerReturn<Vec<qp::BaseQP>> {
+) -> HandlerReturn<Vec<qp::SearchQP>> {
     let response = if let Some(query) = params.get("query") {
         let exam_query_str = params
             .get("exam")
@@ -115,13 +266,51 @@ pub async fn search(
             .map(Exam::try_from)
             .collect::<Result<Vec<Exam>, _>>()
         {
+            let tag_filter = params
+                .get("tags")
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(tags::normalize_tag_name)
+                        .filter(|name| !name.is_empty())
+                        .collect::<HashSet<String>>()
+                        .into_iter()
+                        .collect::<Vec<String>>()
+                })
+                .unwrap_or_default();
+            let department_filter = params
+                .get("department")
+                .map(|value| departments::normalize_department_code(value))
+                .filter(|code| !code.is_empty());
+
             let papers = state
                 .db
-                .search_papers(query, exam_filter)
-                .await?
+                .search_papers(
+                    query,
+                    exam_filter,
+                    &tag_filter,
+                    department_filter.as_deref(),
+                )
+                .await?;
+
+            let paper_ids = papers.iter().map(|paper| paper.id).collect::<Vec<i32>>();
+            let mut attachments = HashMap::<i32, Vec<qp::Attachment>>::new();
+            for attachment in state.db.get_published_attachments(&paper_ids).await? {
+                attachments
+                    .entry(attachment.attachment_of)
+                    .or_default()
+                    .push(attachment.with_url(&state.env_vars)?);
+            }
+
+            let papers = papers
                 .into_iter()
-                .map(|paper| paper.with_url(&state.env_vars))
-                .collect::<Result<Vec<qp::BaseQP>, color_eyre::eyre::Error>>()?;
+                .map(|paper| {
+                    Ok(qp::SearchQP {
+                        attachments: attachments.remove(&paper.id).unwrap_or_default(),
+                        qp: paper.with_url(&state.env_vars)?,
+                    })
+                })
+                .collect::<Result<Vec<qp::SearchQP>, color_eyre::eyre::Error>>()?;
 
             Ok(BackendResponse::ok(
                 format!("Successfully fetched {} papers.", papers.len()),
@@ -162,7 +351,7 @@ pub async fn oauth(
     State(state): HandlerState,
     Json(body): Json<OAuthReq>,
 ) -> HandlerReturn<OAuthRes> {
-    if let Some(token) = auth::authenticate_user(&body.code, &state.env_vars).await? {
+    if let Some(token) = auth::authenticate_user(&body.code, &state.env_vars, &state.db).await? {
         Ok(BackendResponse::ok(
             "Successfully authorized the user.".into(),
             OAuthRes { token },
@@ -180,15 +369,17 @@ pub async fn oauth(
 pub struct ProfileRes {
     token: String,
     username: String,
+    role: Role,
 }
 
-/// Returns a user's profile (the JWT and username) if authorized and the token is valid. Can be used to check if the user is logged in.
+/// Returns a user's profile (the JWT, username, and role) if authorized and the token is valid. Can be used to check if the user is logged in.
 pub async fn profile(Extension(auth): Extension<Auth>) -> HandlerReturn<ProfileRes> {
     Ok(BackendResponse::ok(
         "Successfully authorized the user.".into(),
         ProfileRes {
             token: auth.jwt,
             username: auth.username,
+            role: auth.role,
         },
     ))
 }
@@ -205,299 +396,2105 @@ pub struct EditReq {
     pub note: Option<String>,
     pub approve_status: Option<bool>,
     pub replace: Vec<i32>,
+    #[serde(default)]
+    /// Edit the paper even if it is claimed by another admin
+    pub force: bool,
+    /// The version of the paper the edit was made on. Can also be sent using the `If-Match` header. Required by the edit endpoint.
+    pub version: Option<i32>,
+    #[serde(skip)]
+    /// Store the paper's file as a new file revision, keeping the current file. Set when the contents of the file change.
+    pub new_file_revision: bool,
+    #[serde(default)]
+    /// The names of the tags to add to the paper. The tags must exist.
+    pub add_tags: Vec<String>,
+    #[serde(default)]
+    /// The names of the tags to remove from the paper
+    pub remove_tags: Vec<String>,
+}
+
+/// Checks whether a paper can be edited by the given admin. Quarantined papers cannot be edited, and papers claimed by another admin can only be edited if `force` is set.
+///
+/// Returns an error message and status code if the paper cannot be edited.
+fn check_editable(
+    paper: &AdminDashboardQP,
+    username: &str,
+    force: bool,
+) -> Option<(String, StatusCode)> {
+    if paper.is_quarantined {
+        Some((
+            "Error: Quarantined papers cannot be edited or approved.".into(),
+            StatusCode::BAD_REQUEST,
+        ))
+    } else if !force && paper.is_claimed_by_other(username) {
+        Some((
+            format!(
+                "Error: The paper is being reviewed by {}. Set `force` to edit it anyway.",
+                paper.claimed_by.as_deref().unwrap_or_default()
+            ),
+            StatusCode::CONFLICT,
+        ))
+    } else {
+        None
+    }
 }
 
 /// Paper edit endpoint (for admin dashboard)
 /// Takes a JSON request body. The `id` field is required.
+/// The `version` field (or the `If-Match` header) is required, the edit is rejected with the current details of the paper if the paper has changed since that version.
 /// Other optional fields can be set to change that particular value in the paper.
 ///
 /// Request format - [`EditReq`]
 pub async fn edit(
     Extension(auth): Extension<Auth>,
     State(state): HandlerState,
-    Json(body): Json<EditReq>,
+    headers: HeaderMap,
+    Json(mut body): Json<EditReq>,
 ) -> HandlerReturn<AdminDashboardQP> {
-    // Edit the database entry
-    let (tx, old_filelink, new_qp) = state
-        .db
-        .edit_paper(body, &auth.username, &state.env_vars)
-        .await?;
-
-    // Copy the actual file
-    let old_filepath = state.env_vars.paths.get_path_from_slug(&old_filelink);
-    let new_filepath = state.env_vars.paths.get_path_from_slug(&new_qp.qp.filelink);
-
-    if old_filepath != new_filepath {
-        if let Err(e) = fs::copy(old_filepath, new_filepath).await {
-            tracing::error!("Error copying file: {}", e);
-
-            tx.rollback().await?;
-            Ok(BackendResponse::error(
-                "Error copying question paper file.".into(),
-                StatusCode::INTERNAL_SERVER_ERROR,
-            ))
-        } else {
-            // Commit the transaction
-            tx.commit().await?;
-
-            Ok(BackendResponse::ok(
-                "Successfully updated paper details.".into(),
-                new_qp.with_url(&state.env_vars)?,
-            ))
-        }
-    } else {
-        // Commit the transaction
-        tx.commit().await?;
-        Ok(BackendResponse::ok(
-            "Successfully updated paper details.".into(),
-            new_qp.with_url(&state.env_vars)?,
-        ))
+    if body.version.is_none() {
+        body.version = get_if_match_version(&headers);
     }
-}
-
-#[derive(Deserialize)]
-/// The details for an uploaded question paper file
-pub struct FileDetails {
-    pub course_code: String,
-    pub course_name: String,
-    pub year: i32,
-    pub exam: String,
-    pub semester: String,
-    pub filename: String,
-    pub note: String,
-}
 
-/// 10 MiB file size limit
-const FILE_SIZE_LIMIT: usize = 10 << 20;
-#[derive(Serialize)]
-/// The status of an uploaded question paper file
-pub struct UploadStatus {
-    /// The filename
-    filename: String,
-    /// Whether the file was successfully uploaded
-    status: &'static str,
-    /// A message describing the status
-    message: String,
-}
+    let Some(version) = body.version else {
+        return Ok(BackendResponse::error(
+            "Error: The version of the paper being edited is required.".into(),
+            StatusCode::PRECONDITION_REQUIRED,
+        ));
+    };
 
-impl UploadStatus {
-    fn ok(filename: String) -> Self {
-        Self {
-            filename,
-            status: "success",
-            message: "Successfully uploaded paper.".into(),
-        }
+    let paper = state.db.get_paper_by_id(body.id).await?;
+    if let Some((message, status_code)) = check_editable(&paper, &auth.username, body.force) {
+        return Ok(BackendResponse::error(message, status_code));
     }
 
-    fn error(filename: String, message: String) -> Self {
-        Self {
-            filename,
-            status: "error",
-            message,
-        }
+    if paper.version != version {
+        return Ok(BackendResponse::error_with_data(
+            "Error: The paper was changed by someone else. Review the current details and try again.".into(),
+            paper.with_url(&state.env_vars)?,
+            StatusCode::CONFLICT,
+        ));
     }
-}
-
-/// Uploads question papers to the server
-///
-/// Request format - Multipart form with a `file_details` field of the format [`FileDetails`]
-pub async fn upload(
-    State(state): HandlerState,
-    mut multipart: Multipart,
-) -> HandlerReturn<Vec<UploadStatus>> {
-    let mut files = Vec::<(HeaderMap, Bytes)>::new();
-    let mut file_details: String = "".into();
-
-    while let Some(field) = multipart.next_field().await? {
-        let name = field
-            .name()
-            .ok_or(eyre!("Error parsing file upload multipart field."))?
-            .to_string();
 
-        if name == "files" {
-            files.push((field.headers().clone(), field.bytes().await?));
-        } else if name == "file_details" {
-            if file_details.is_empty() {
-                file_details = field.text().await?;
-            } else {
-                return Ok(BackendResponse::error(
-                    "Error: Multiple `file_details` fields found.".into(),
-                    StatusCode::BAD_REQUEST,
-                ));
-            }
-        }
+    if let Some(message) = check_tags(&state, &mut body.add_tags, &mut body.remove_tags).await? {
+        return Ok(BackendResponse::error(message, StatusCode::BAD_REQUEST));
     }
 
-    let files = files;
-    let file_details: Vec<FileDetails> = serde_json::from_str(&file_details)?;
+    apply_edit(
+        &state,
+        body,
+        None,
+        &auth.username,
+        "Successfully updated paper details.",
+    )
+    .await
+}
 
-    if files.len() > state.env_vars.max_upload_limit {
-        return Ok(BackendResponse::error(
-            format!(
-                "Only upto {} files can be uploaded. Found {}.",
-                state.env_vars.max_upload_limit,
-                files.len()
-            ),
-            StatusCode::BAD_REQUEST,
-        ));
+/// Normalizes the names of the tags to be added to and removed from a paper (see [`tags::normalize_tag_name`]).
+///
+/// Returns an error message if any of the tags to be added does not exist.
+async fn check_tags(
+    state: &RouterState,
+    add_tags: &mut Vec<String>,
+    remove_tags: &mut Vec<String>,
+) -> Result<Option<String>> {
+    for names in [&mut *add_tags, &mut *remove_tags] {
+        *names = names
+            .iter()
+            .map(|name| tags::normalize_tag_name(name))
+            .collect();
     }
 
-    if files.len() != file_details.len() {
-        return Ok(BackendResponse::error(
-            "Error: Number of files and file details array length do not match.".into(),
-            StatusCode::BAD_REQUEST,
-        ));
+    let unknown_tags = state.db.get_unknown_tags(add_tags).await?;
+    if unknown_tags.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(format!(
+            "Error: Unknown tags: {}.",
+            unknown_tags.join(", ")
+        )))
     }
+}
 
-    let files_iter = files.into_iter().zip(file_details.into_iter());
-    let mut upload_statuses = Vec::<UploadStatus>::new();
-
-    for ((file_headers, file_data), details) in files_iter {
-        let filename = details.filename.clone();
+/// Parses the version of a paper sent using the `If-Match` header. ETags may be quoted and weak (`W/"3"`).
+fn get_if_match_version(headers: &HeaderMap) -> Option<i32> {
+    headers
+        .get("If-Match")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            value
+                .trim_start_matches("W/")
+                .trim_matches('"')
+                .parse::<i32>()
+                .ok()
+        })
+}
 
-        if file_data.len() > FILE_SIZE_LIMIT {
-            upload_statuses.push(UploadStatus::error(
-                filename,
-                format!(
-                    "File size too big. Only files upto {} MiB are allowed.",
-                    FILE_SIZE_LIMIT >> 20
-                ),
-            ));
-            continue;
-        }
+/// Locks a paper until the end of the transaction and checks that it has not changed since the given version. The version checked before the transaction may be outdated by the time the paper is edited (eg: two concurrent edits of the same version).
+///
+/// Returns the error response if the paper does not exist or has changed (`409 Conflict`, with the current details of the paper). The transaction must then be rolled back.
+async fn lock_paper_version(
+    state: &RouterState,
+    tx: &mut Transaction<'_, Postgres>,
+    id: i32,
+    version: i32,
+) -> Result<Option<(StatusCode, BackendResponse<AdminDashboardQP>)>> {
+    match state.db.lock_paper_in_tx(tx, id).await? {
+        Some(current) if current.version == version => Ok(None),
+        Some(current) => Ok(Some(BackendResponse::error_with_data(
+            "Error: The paper was changed by someone else. Review the current details and try again.".into(),
+            current.with_url(&state.env_vars)?,
+            StatusCode::CONFLICT,
+        ))),
+        None => Ok(Some(BackendResponse::error(
+            "Error: The paper does not exist.".into(),
+            StatusCode::NOT_FOUND,
+        ))),
+    }
+}
 
-        if let Some(content_type) = file_headers.get("content-type") {
-            if content_type != "application/pdf" {
-                upload_statuses.push(UploadStatus::error(
-                    filename,
-                    "Only PDFs are supported.".into(),
-                ));
-                continue;
-            }
-        } else {
-            upload_statuses.push(UploadStatus::error(
-                filename,
-                "`content-type` header not found. File type could not be determined.".into(),
-            ));
-            continue;
+/// Edits a paper's details in the database and copies the paper's file to its new filelink (if changed). Used by the edit and revert endpoints. If the edit request has a version, it is checked again inside the transaction (see [`lock_paper_version`]).
+///
+/// If `file_source` (a slug) is set, that file is copied instead of the paper's current file and the paper's content hash and text are updated.
+async fn apply_edit(
+    state: &RouterState,
+    edit_req: EditReq,
+    file_source: Option<String>,
+    username: &str,
+    message: &str,
+) -> HandlerReturn<AdminDashboardQP> {
+    let mut tx = state.db.begin_transaction().await?;
+    if let Some(version) = edit_req.version {
+        if let Some(response) = lock_paper_version(state, &mut tx, edit_req.id, version).await? {
+            tx.rollback().await?;
+            return Ok(response);
         }
+    }
 
-        // Insert the db entry
-        let (mut tx, id) = state.db.insert_new_uploaded_qp(details).await?;
-
-        // Create the new filelink (slug)
-        let filelink_slug = state
-            .env_vars
-            .paths
-            .get_slug(&format!("{}.pdf", id), PaperCategory::Unapproved);
+    // Edit the database entry
+    let (old_filelink, new_qp) = state
+        .db
+        .edit_paper_in_tx(
+            &mut tx,
+            edit_req,
+            username,
+            AuditAction::Edit,
+            &state.env_vars,
+        )
+        .await?;
 
-        // Update the filelink in the db
-        if state
-            .db
-            .update_filelink(&mut tx, id, &filelink_slug)
-            .await
-            .is_ok()
-        {
-            let filepath = state.env_vars.paths.get_path_from_slug(&filelink_slug);
+    // Copy the actual file
+    let old_filepath = state
+        .env_vars
+        .paths
+        .get_path_from_slug(file_source.as_deref().unwrap_or(&old_filelink));
+    let new_filepath = state.env_vars.paths.get_path_from_slug(&new_qp.qp.filelink);
 
-            // Write the file data
-            if fs::write(&filepath, file_data).await.is_ok() {
-                if tx.commit().await.is_ok() {
-                    upload_statuses.push(UploadStatus::ok(filename));
-                    continue;
-                } else {
-                    // Transaction commit failed, delete the file
-                    fs::remove_file(filepath).await?;
-                    upload_statuses.push(UploadStatus::error(
-                        filename,
-                        "Error: Database transaction failed.".into(),
-                    ));
-                    continue;
-                }
-            } else {
-                tx.rollback().await?;
-            }
+    if old_filepath != new_filepath {
+        if let Err(e) = fs::copy(old_filepath, &new_filepath).await {
+            tracing::error!("Error copying file: {}", e);
 
-            // If the write fails, rollback the transaction, else commit it.
-        } else {
             tx.rollback().await?;
-
-            upload_statuses.push(UploadStatus::error(
-                filename,
-                "Error updating the filelink".into(),
+            return Ok(BackendResponse::error(
+                "Error copying question paper file.".into(),
+                StatusCode::INTERNAL_SERVER_ERROR,
             ));
-            continue;
         }
-
-        upload_statuses.push(UploadStatus::error(filename,
-            "THIS SHOULD NEVER HAPPEN. REPORT IMMEDIATELY. ~~ALSO THIS WOULDN'T HAPPEN IF RUST HAD STABLE ASYNC CLOSURES.~~ CORRECTION: ASYNC CLOSURES ARE STABLE. THE ALTERNATIVE IS INSANE. THIS IS BETTER.".into()));
     }
 
-    let total_count = state.db.get_unapproved_papers_count().await?;
-    let count = upload_statuses.len();
-    let message = format!(
-        "🔔 {} uploaded to IQPS!\n\n<https://qp.metakgp.org/admin|Review> | Total Unapproved papers: *{}*",
-        if count == 1 {
-            "A new paper was".into()
-        } else {
-            format!("{} new papers were", count)
-        },
-        total_count
-    );
+    if file_source.is_some() {
+        let (content_hash, content_text) =
+            workers::get_paper_content(&fs::read(&new_filepath).await?).await;
+        state
+            .db
+            .set_paper_content(&mut tx, new_qp.qp.id, &content_hash, &content_text)
+            .await?;
+    }
 
-    let _ = send_slack_message(&state.env_vars.slack_webhook_url, &message).await;
+    // Commit the transaction
+    tx.commit().await?;
 
     Ok(BackendResponse::ok(
-        format!("Successfully processed {} files", upload_statuses.len()),
-        upload_statuses,
+        message.into(),
+        new_qp.with_url(&state.env_vars)?,
     ))
 }
 
+/// Default duration of a claim on a paper, in minutes
+const DEFAULT_CLAIM_MINUTES: i32 = 15;
+/// Maximum duration of a claim on a paper, in minutes
+const MAX_CLAIM_MINUTES: i32 = 120;
+
 #[derive(Deserialize)]
-/// The request format for the delete endpoint
-pub struct DeleteReq {
+/// The request format for the claim endpoint
+pub struct ClaimReq {
     id: i32,
+    /// The duration of the claim in minutes (15 by default, at most 120)
+    minutes: Option<i32>,
 }
 
-/// (Soft) Deletes a given paper.
+/// Claims a paper for review, so that other admins see it as claimed and cannot edit it (unless forced). Claiming a paper already claimed by the same admin extends the claim. Claims expire on their own.
 ///
-/// Request format - [`DeleteReq`]
-pub async fn delete(State(state): HandlerState, Json(body): Json<DeleteReq>) -> HandlerReturn<()> {
-    let paper_deleted = state.db.soft_delete(body.id).await?;
+/// Request format - [`ClaimReq`]
+pub async fn claim(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    Json(body): Json<ClaimReq>,
+) -> HandlerReturn<AdminDashboardQP> {
+    let minutes = body.minutes.unwrap_or(DEFAULT_CLAIM_MINUTES);
+    if !(1..=MAX_CLAIM_MINUTES).contains(&minutes) {
+        return Ok(BackendResponse::error(
+            format!(
+                "Error: A claim must be between 1 and {} minutes long.",
+                MAX_CLAIM_MINUTES
+            ),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
 
-    if paper_deleted {
+    if let Some(paper) = state
+        .db
+        .claim_paper(body.id, &auth.username, minutes)
+        .await?
+    {
         Ok(BackendResponse::ok(
-            "Succesfully deleted the paper.".into(),
-            (),
+            format!("Successfully claimed the paper for {} minutes.", minutes),
+            paper.with_url(&state.env_vars)?,
         ))
+    } else if let Ok(paper) = state.db.get_paper_by_id(body.id).await {
+        match paper.claimed_by {
+            Some(claimed_by) => Ok(BackendResponse::error(
+                format!("Error: The paper is being reviewed by {}.", claimed_by),
+                StatusCode::CONFLICT,
+            )),
+            None => Ok(BackendResponse::error(
+                "Error: Deleted papers cannot be claimed.".into(),
+                StatusCode::BAD_REQUEST,
+            )),
+        }
     } else {
         Ok(BackendResponse::error(
-            "No paper was changed. Either the paper does not exist, or is already deleted.".into(),
-            StatusCode::BAD_REQUEST,
+            "Error: Paper not found.".into(),
+            StatusCode::NOT_FOUND,
         ))
     }
 }
 
 #[derive(Deserialize)]
-/// The request format for the hard delete endpoint
-pub struct HardDeleteReq {
-    ids: Vec<i32>,
+/// The request format for the unclaim endpoint
+pub struct UnclaimReq {
+    id: i32,
 }
 
-#[derive(Serialize)]
-/// The status of a paper to be deleted
-pub struct DeleteStatus {
-    id: i32,
-    status: &'static str,
-    message: &'static str,
+/// Releases the user's claim on a paper.
+///
+/// Request format - [`UnclaimReq`]
+pub async fn unclaim(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    Json(body): Json<UnclaimReq>,
+) -> HandlerReturn<()> {
+    if state.db.unclaim_paper(body.id, &auth.username).await? {
+        Ok(BackendResponse::ok(
+            "Successfully released the claim on the paper.".into(),
+            (),
+        ))
+    } else {
+        Ok(BackendResponse::error(
+            "Error: You have not claimed this paper.".into(),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
 }
 
-impl DeleteStatus {
-    fn ok(id: i32) -> Self {
-        Self {
+/// Fetches all the saved versions of a paper's details, latest first.
+///
+/// # Request Query Parameters
+/// * `id`: The paper's id
+pub async fn get_history(
+    State(state): HandlerState,
+    Query(params): Query<HashMap<String, String>>,
+) -> HandlerReturn<Vec<PaperVersion>> {
+    let Some(Ok(id)) = params.get("id").map(|id| id.parse::<i32>()) else {
+        return Ok(BackendResponse::error(
+            "Invalid or missing paper id.".into(),
+            StatusCode::BAD_REQUEST,
+        ));
+    };
+
+    let versions = state.db.get_paper_history(id).await?;
+
+    Ok(BackendResponse::ok(
+        format!("Successfully fetched {} versions.", versions.len()),
+        versions,
+    ))
+}
+
+#[derive(Deserialize)]
+/// The request format for the revert endpoint
+pub struct RevertReq {
+    /// The id of the paper
+    id: i32,
+    /// The id of the version to revert to
+    version_id: i32,
+    /// The current version of the paper the revert was made on (see [`EditReq::version`]). Can also be sent using the `If-Match` header. Required.
+    version: Option<i32>,
+    #[serde(default)]
+    /// Revert the paper even if it is claimed by another admin
+    force: bool,
+}
+
+/// Reverts a paper's details to a saved version. The revert is saved as a new version, so it can be undone too.
+///
+/// Like the edit endpoint, the revert is rejected with the current details of the paper if the paper has changed since the given version.
+///
+/// Request format - [`RevertReq`]
+pub async fn revert(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    headers: HeaderMap,
+    Json(body): Json<RevertReq>,
+) -> HandlerReturn<AdminDashboardQP> {
+    let Some(current_version) = body.version.or_else(|| get_if_match_version(&headers)) else {
+        return Ok(BackendResponse::error(
+            "Error: The version of the paper being reverted is required.".into(),
+            StatusCode::PRECONDITION_REQUIRED,
+        ));
+    };
+
+    let Some(version) = state
+        .db
+        .get_paper_version(body.version_id)
+        .await?
+        .filter(|version| version.paper_id == body.id)
+    else {
+        return Ok(BackendResponse::error(
+            "Error: Version not found for this paper.".into(),
+            StatusCode::NOT_FOUND,
+        ));
+    };
+
+    let paper = state.db.get_paper_by_id(body.id).await?;
+    if let Some((message, status_code)) = check_editable(&paper, &auth.username, body.force) {
+        return Ok(BackendResponse::error(message, status_code));
+    }
+
+    if paper.version != current_version {
+        return Ok(BackendResponse::error_with_data(
+            "Error: The paper was changed by someone else. Review the current details and try again.".into(),
+            paper.with_url(&state.env_vars)?,
+            StatusCode::CONFLICT,
+        ));
+    }
+
+    let edit_req = EditReq {
+        id: version.paper_id,
+        course_code: Some(version.course_code),
+        course_name: Some(version.course_name),
+        year: Some(version.year),
+        semester: Some(String::from(&version.semester)),
+        exam: Some(String::from(&version.exam)),
+        note: Some(version.note),
+        approve_status: Some(version.approve_status),
+        replace: Vec::new(),
+        force: body.force,
+        version: Some(current_version),
+        new_file_revision: version.file_revision != paper.file_revision,
+        add_tags: Vec::new(),
+        remove_tags: Vec::new(),
+    };
+
+    // Restore the file of the version if it was rewritten since
+    let file_source = edit_req.new_file_revision.then_some(version.filelink);
+
+    apply_edit(
+        &state,
+        edit_req,
+        file_source,
+        &auth.username,
+        "Successfully reverted paper details.",
+    )
+    .await
+}
+
+#[derive(Deserialize)]
+/// A paper to be edited in a bulk edit
+pub struct BulkEditPaper {
+    id: i32,
+    /// The version of the paper the edit was made on (see [`EditReq::version`])
+    version: i32,
+}
+
+#[derive(Deserialize)]
+/// The request format for the bulk edit endpoint. The optional fields are applied to every paper, like in [`EditReq`].
+pub struct BulkEditReq {
+    papers: Vec<BulkEditPaper>,
+    course_code: Option<String>,
+    course_name: Option<String>,
+    year: Option<i32>,
+    semester: Option<String>,
+    exam: Option<String>,
+    note: Option<String>,
+    approve_status: Option<bool>,
+    #[serde(default)]
+    /// The names of the tags to add to every paper
+    add_tags: Vec<String>,
+    #[serde(default)]
+    /// The names of the tags to remove from every paper
+    remove_tags: Vec<String>,
+    #[serde(default)]
+    /// Edit the papers even if they are claimed by other admins
+    force: bool,
+}
+
+impl BulkEditReq {
+    /// Returns the edit request for a single paper
+    fn edit_req(&self, paper: &BulkEditPaper) -> EditReq {
+        EditReq {
+            id: paper.id,
+            course_code: self.course_code.clone(),
+            course_name: self.course_name.clone(),
+            year: self.year,
+            semester: self.semester.clone(),
+            exam: self.exam.clone(),
+            note: self.note.clone(),
+            approve_status: self.approve_status,
+            replace: Vec::new(),
+            force: self.force,
+            version: Some(paper.version),
+            new_file_revision: false,
+            add_tags: self.add_tags.clone(),
+            remove_tags: self.remove_tags.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+/// The status of a paper to be edited in a bulk edit
+pub struct EditStatus {
+    id: i32,
+    status: &'static str,
+    message: String,
+}
+
+impl EditStatus {
+    fn ok(id: i32) -> Self {
+        Self {
+            id,
+            status: "success",
+            message: "Successfully updated paper details.".into(),
+        }
+    }
+
+    fn error(id: i32, message: &str) -> Self {
+        Self {
+            id,
+            status: "error",
+            message: message.into(),
+        }
+    }
+}
+
+/// Applies the same edit to multiple papers (eg: approving or tagging a batch of papers). The papers are edited in a single transaction, if any paper fails to be edited, no paper is edited.
+///
+/// Like the edit endpoint, the version of each paper is required. The papers are locked and checked inside the transaction, if any paper has changed since its version or is claimed by another admin (unless `force` is set), no paper is edited and a `409 Conflict` is returned. The statuses are returned in the order of the paper ids. If a paper fails to be edited after the checks, `422 Unprocessable Entity` is returned (`500 Internal Server Error` if the transaction fails to commit), along with the statuses.
+///
+/// Request format - [`BulkEditReq`]
+pub async fn bulk_edit(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    Json(mut body): Json<BulkEditReq>,
+) -> HandlerReturn<Vec<EditStatus>> {
+    if let Some(message) = check_tags(&state, &mut body.add_tags, &mut body.remove_tags).await? {
+        return Ok(BackendResponse::error(message, StatusCode::BAD_REQUEST));
+    }
+
+    // Lock the papers in a consistent order to avoid deadlocks between bulk edits
+    body.papers.sort_by_key(|paper| paper.id);
+    body.papers.dedup_by_key(|paper| paper.id);
+
+    let mut tx = state.db.begin_transaction().await?;
+    let mut edit_statuses = Vec::<EditStatus>::new();
+    let mut error_status_code = None;
+
+    // Check all the papers before editing any of them
+    for paper in &body.papers {
+        let (edit_status, status_code) = match state.db.lock_paper_in_tx(&mut tx, paper.id).await? {
+            Some(current) => {
+                if let Some((message, status_code)) =
+                    check_editable(&current, &auth.username, body.force)
+                {
+                    (EditStatus::error(paper.id, &message), Some(status_code))
+                } else if current.version != paper.version {
+                    (
+                        EditStatus::error(
+                            paper.id,
+                            "The paper was changed by someone else. Review the current details and try again.",
+                        ),
+                        Some(StatusCode::CONFLICT),
+                    )
+                } else {
+                    (EditStatus::ok(paper.id), None)
+                }
+            }
+            None => (
+                EditStatus::error(paper.id, "Paper does not exist."),
+                Some(StatusCode::NOT_FOUND),
+            ),
+        };
+
+        edit_statuses.push(edit_status);
+        // A conflict is reported over other errors
+        if status_code.is_some() && error_status_code != Some(StatusCode::CONFLICT) {
+            error_status_code = status_code;
+        }
+    }
+
+    if let Some(status_code) = error_status_code {
+        tx.rollback().await?;
+
+        return Ok(BackendResponse::error_with_data(
+            "No papers were edited.".into(),
+            skip_unfailed(edit_statuses),
+            status_code,
+        ));
+    }
+
+    // The files copied to new filelinks, removed if the transaction is rolled back
+    let mut copied_files = Vec::<PathBuf>::new();
+    let mut failed = false;
+
+    for (status, paper) in edit_statuses.iter_mut().zip(&body.papers) {
+        let edit_result = state
+            .db
+            .edit_paper_in_tx(
+                &mut tx,
+                body.edit_req(paper),
+                &auth.username,
+                AuditAction::Edit,
+                &state.env_vars,
+            )
+            .await;
+
+        let (old_filelink, new_qp) = match edit_result {
+            Ok(edit) => edit,
+            Err(e) => {
+                tracing::error!("Error editing paper {}: {}", status.id, e);
+
+                *status = EditStatus::error(status.id, "Error updating paper details.");
+                failed = true;
+                break;
+            }
+        };
+
+        let old_filepath = state.env_vars.paths.get_path_from_slug(&old_filelink);
+        let new_filepath = state.env_vars.paths.get_path_from_slug(&new_qp.qp.filelink);
+
+        if old_filepath != new_filepath {
+            if let Err(e) = fs::copy(old_filepath, &new_filepath).await {
+                tracing::error!("Error copying file: {}", e);
+
+                *status = EditStatus::error(status.id, "Error copying question paper file.");
+                failed = true;
+                break;
+            }
+
+            copied_files.push(new_filepath);
+        }
+    }
+
+    if failed {
+        tx.rollback().await?;
+        for filepath in copied_files {
+            let _ = fs::remove_file(filepath).await;
+        }
+
+        return Ok(BackendResponse::error_with_data(
+            "No papers were edited.".into(),
+            skip_unfailed(edit_statuses),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        ));
+    }
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Error committing the bulk edit: {}", e);
+
+        for filepath in copied_files {
+            let _ = fs::remove_file(filepath).await;
+        }
+
+        return Ok(BackendResponse::error_with_data(
+            "No papers were edited.".into(),
+            edit_statuses
+                .into_iter()
+                .map(|status| EditStatus::error(status.id, "Error committing the edits."))
+                .collect(),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    Ok(BackendResponse::ok(
+        format!("Successfully edited {} papers.", edit_statuses.len()),
+        edit_statuses,
+    ))
+}
+
+/// Marks the papers that did not fail in a failed bulk edit as skipped
+fn skip_unfailed(edit_statuses: Vec<EditStatus>) -> Vec<EditStatus> {
+    edit_statuses
+        .into_iter()
+        .map(|status| {
+            if status.status == "success" {
+                EditStatus::error(status.id, "Not edited since another paper failed.")
+            } else {
+                status
+            }
+        })
+        .collect()
+}
+
+#[derive(Deserialize, Default)]
+/// The papers (by id) to take each field of a merged paper from. Fields that are not set are taken from the keeper.
+pub struct MergeSources {
+    course_code: Option<i32>,
+    course_name: Option<i32>,
+    year: Option<i32>,
+    semester: Option<i32>,
+    exam: Option<i32>,
+    note: Option<i32>,
+    /// The paper whose file is kept
+    file: Option<i32>,
+}
+
+impl MergeSources {
+    /// Returns the ids of all the papers used as sources
+    fn ids(&self) -> impl Iterator<Item = i32> {
+        [
+            self.course_code,
+            self.course_name,
+            self.year,
+            self.semester,
+            self.exam,
+            self.note,
+            self.file,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+#[derive(Deserialize)]
+/// The request format for the merge endpoint
+pub struct MergeReq {
+    /// The paper that is kept
+    keeper_id: i32,
+    /// The papers merged into the keeper, moved to the trash
+    duplicate_ids: Vec<i32>,
+    #[serde(default)]
+    sources: MergeSources,
+    #[serde(default)]
+    /// Merge the papers even if they are claimed by other admins
+    force: bool,
+}
+
+/// Merges duplicate papers into a single paper (the keeper). Each field of the keeper and its file can be taken from any of the duplicates. The duplicates are moved to the trash with a pointer to the keeper.
+///
+/// The keeper's changes are saved in its history, and the merge is recorded in the audit log for every paper.
+///
+/// Request format - [`MergeReq`]
+pub async fn merge(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    Json(body): Json<MergeReq>,
+) -> HandlerReturn<AdminDashboardQP> {
+    let duplicate_ids = body.duplicate_ids.iter().copied().collect::<HashSet<i32>>();
+    if duplicate_ids.is_empty() {
+        return Ok(BackendResponse::error(
+            "Error: No duplicates to merge.".into(),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+    if duplicate_ids.contains(&body.keeper_id) {
+        return Ok(BackendResponse::error(
+            "Error: A paper cannot be merged into itself.".into(),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+    if body
+        .sources
+        .ids()
+        .any(|id| id != body.keeper_id && !duplicate_ids.contains(&id))
+    {
+        return Ok(BackendResponse::error(
+            "Error: Fields can only be taken from the papers being merged.".into(),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let mut papers = HashMap::<i32, AdminDashboardQP>::new();
+    for &id in duplicate_ids.iter().chain([&body.keeper_id]) {
+        let paper = match state.db.get_paper_by_id(id).await {
+            Ok(paper) if !paper.is_deleted => paper,
+            _ => {
+                return Ok(BackendResponse::error(
+                    format!("Error: Paper {} does not exist or is deleted.", id),
+                    StatusCode::NOT_FOUND,
+                ))
+            }
+        };
+
+        if let Some((message, status_code)) = check_editable(&paper, &auth.username, body.force) {
+            return Ok(BackendResponse::error(
+                format!("Paper {}: {}", id, message),
+                status_code,
+            ));
+        }
+
+        papers.insert(id, paper);
+    }
+
+    let source = |source_id: Option<i32>| &papers[&source_id.unwrap_or(body.keeper_id)].qp;
+    let keeper = &papers[&body.keeper_id];
+    let edit_req = EditReq {
+        id: body.keeper_id,
+        course_code: Some(source(body.sources.course_code).course_code.clone()),
+        course_name: Some(source(body.sources.course_name).course_name.clone()),
+        year: Some(source(body.sources.year).year),
+        semester: Some(String::from(&source(body.sources.semester).semester)),
+        exam: Some(String::from(&source(body.sources.exam).exam)),
+        note: Some(source(body.sources.note).note.clone()),
+        approve_status: None,
+        replace: Vec::new(),
+        force: body.force,
+        version: Some(keeper.version),
+        new_file_revision: body
+            .sources
+            .file
+            .is_some_and(|file_source_id| file_source_id != body.keeper_id),
+        // The keeper gets the tags of all the duplicates
+        add_tags: duplicate_ids
+            .iter()
+            .flat_map(|id| papers[id].qp.tags.clone())
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .collect(),
+        remove_tags: Vec::new(),
+    };
+
+    let file_source_id = body.sources.file.unwrap_or(body.keeper_id);
+    let file_source = &papers[&file_source_id];
+    let duplicate_ids = duplicate_ids.into_iter().collect::<Vec<i32>>();
+
+    let (tx, old_filelink, new_qp) = state
+        .db
+        .merge_papers(
+            edit_req,
+            &duplicate_ids,
+            file_source_id,
+            &auth.username,
+            &state.env_vars,
+        )
+        .await?;
+
+    // Copy the kept file to the keeper's filelink
+    let source_filepath = if file_source_id == body.keeper_id {
+        state.env_vars.paths.get_path_from_slug(&old_filelink)
+    } else {
+        file_source.get_system_path(&state.env_vars)
+    };
+    let new_filepath = state.env_vars.paths.get_path_from_slug(&new_qp.qp.filelink);
+
+    if source_filepath != new_filepath {
+        if let Err(e) = fs::copy(source_filepath, new_filepath).await {
+            tracing::error!("Error copying file: {}", e);
+
+            tx.rollback().await?;
+            return Ok(BackendResponse::error(
+                "Error copying question paper file.".into(),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ));
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(BackendResponse::ok(
+        format!("Successfully merged {} papers.", duplicate_ids.len()),
+        new_qp.with_url(&state.env_vars)?,
+    ))
+}
+
+#[derive(Deserialize)]
+/// A page of a paper's file, used by the page editing endpoints
+pub struct PageReq {
+    /// The page number in the paper's current file (starting from 1)
+    page: u32,
+    #[serde(default)]
+    /// The clockwise rotation (in degrees, a multiple of 90) added to the page
+    rotation: i64,
+}
+
+/// Reads a paper's file and builds a new PDF with the given pages. Returns an error message if the file can not be read or the pages are invalid.
+async fn rewrite_pages(
+    state: &RouterState,
+    paper: &AdminDashboardQP,
+    pages: &[PageReq],
+) -> Result<Vec<u8>, String> {
+    let file_data = fs::read(paper.get_system_path(&state.env_vars))
+        .await
+        .map_err(|e| {
+            tracing::error!("Error reading the file of paper {}: {}", paper.qp.id, e);
+
+            "Error reading question paper file.".to_string()
+        })?;
+
+    let pages = pages
+        .iter()
+        .map(|page| pdf::PageSpec {
+            document: 0,
+            page: page.page,
+            rotation: page.rotation,
+        })
+        .collect::<Vec<pdf::PageSpec>>();
+
+    tokio::task::spawn_blocking(move || pdf::assemble(vec![pdf::load(&file_data)?], &pages))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Error: The pages could not be edited. {}", e))
+}
+
+#[derive(Deserialize)]
+/// The request format for the edit pages endpoint
+pub struct EditPagesReq {
+    id: i32,
+    /// The pages of the new file, in order. Pages of the current file that are not listed are deleted.
+    pages: Vec<PageReq>,
+    /// The version of the paper the edit was made on. Can also be sent using the `If-Match` header. Required.
+    version: Option<i32>,
+    #[serde(default)]
+    /// Edit the paper even if it is claimed by another admin
+    force: bool,
+}
+
+/// Rotates, deletes, or reorders the pages of a paper's file. The new file is saved as a new file revision with the same naming as the edit endpoint, and the previous file is kept so that the paper can be reverted (see [`revert`]).
+///
+/// Request format - [`EditPagesReq`]
+pub async fn edit_pages(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    headers: HeaderMap,
+    Json(body): Json<EditPagesReq>,
+) -> HandlerReturn<AdminDashboardQP> {
+    let Some(version) = body.version.or_else(|| get_if_match_version(&headers)) else {
+        return Ok(BackendResponse::error(
+            "Error: The version of the paper being edited is required.".into(),
+            StatusCode::PRECONDITION_REQUIRED,
+        ));
+    };
+
+    let paper = state.db.get_paper_by_id(body.id).await?;
+    if let Some((message, status_code)) = check_editable(&paper, &auth.username, body.force) {
+        return Ok(BackendResponse::error(message, status_code));
+    }
+
+    if paper.version != version {
+        return Ok(BackendResponse::error_with_data(
+            "Error: The paper was changed by someone else. Review the current details and try again.".into(),
+            paper.with_url(&state.env_vars)?,
+            StatusCode::CONFLICT,
+        ));
+    }
+
+    let file_data = match rewrite_pages(&state, &paper, &body.pages).await {
+        Ok(file_data) => file_data,
+        Err(message) => return Ok(BackendResponse::error(message, StatusCode::BAD_REQUEST)),
+    };
+
+    let edit_req = EditReq {
+        id: body.id,
+        course_code: None,
+        course_name: None,
+        year: None,
+        semester: None,
+        exam: None,
+        note: Some(paper.qp.note),
+        approve_status: None,
+        replace: Vec::new(),
+        force: body.force,
+        version: Some(version),
+        new_file_revision: true,
+        add_tags: Vec::new(),
+        remove_tags: Vec::new(),
+    };
+
+    let mut tx = state.db.begin_transaction().await?;
+
+    // The paper may have changed while its pages were being rewritten
+    if let Some(response) = lock_paper_version(&state, &mut tx, body.id, version).await? {
+        tx.rollback().await?;
+        return Ok(response);
+    }
+
+    let (_, new_qp) = state
+        .db
+        .edit_paper_in_tx(
+            &mut tx,
+            edit_req,
+            &auth.username,
+            AuditAction::EditPages,
+            &state.env_vars,
+        )
+        .await?;
+
+    let (content_hash, content_text) = workers::get_paper_content(&file_data).await;
+    state
+        .db
+        .set_paper_content(&mut tx, new_qp.qp.id, &content_hash, &content_text)
+        .await?;
+
+    let filepath = state.env_vars.paths.get_path_from_slug(&new_qp.qp.filelink);
+    if let Err(e) = fs::write(&filepath, file_data).await {
+        tracing::error!("Error writing file: {}", e);
+
+        tx.rollback().await?;
+        return Ok(BackendResponse::error(
+            "Error writing question paper file.".into(),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    if let Err(e) = tx.commit().await {
+        fs::remove_file(filepath).await?;
+        return Err(e.into());
+    }
+
+    Ok(BackendResponse::ok(
+        "Successfully edited the pages of the paper.".into(),
+        new_qp.with_url(&state.env_vars)?,
+    ))
+}
+
+#[derive(Deserialize)]
+/// The request format for the extract pages endpoint
+pub struct ExtractPagesReq {
+    id: i32,
+    /// The pages of the new paper, in order
+    pages: Vec<PageReq>,
+}
+
+/// Creates a new unapproved paper from some pages of a paper (eg: an answer sheet attached to a question paper). The new paper has the same details as the original paper, which is not changed. The extracted pages can then be deleted from the original paper using [`edit_pages`].
+///
+/// Request format - [`ExtractPagesReq`]
+pub async fn extract_pages(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    Json(body): Json<ExtractPagesReq>,
+) -> HandlerReturn<AdminDashboardQP> {
+    let paper = state.db.get_paper_by_id(body.id).await?;
+    if paper.is_quarantined {
+        return Ok(BackendResponse::error(
+            "Error: Pages cannot be extracted from quarantined papers.".into(),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let file_data = match rewrite_pages(&state, &paper, &body.pages).await {
+        Ok(file_data) => file_data,
+        Err(message) => return Ok(BackendResponse::error(message, StatusCode::BAD_REQUEST)),
+    };
+
+    let details = FileDetails {
+        course_code: paper.qp.course_code,
+        course_name: paper.qp.course_name,
+        year: paper.qp.year,
+        exam: String::from(&paper.qp.exam),
+        semester: String::from(&paper.qp.semester),
+        filename: format!("{}.pdf", body.id),
+        note: paper.qp.note,
+        files: Vec::new(),
+        attachment_of: None,
+        attachment_kind: None,
+    };
+
+    let (mut tx, id) = state.db.insert_new_uploaded_qp(details).await?;
+
+    let filelink_slug = state
+        .env_vars
+        .paths
+        .get_slug(&format!("{}.pdf", id), PaperCategory::Unapproved);
+    let (content_hash, content_text) = workers::get_paper_content(&file_data).await;
+
+    state
+        .db
+        .update_filelink(&mut tx, id, &filelink_slug)
+        .await?;
+    state
+        .db
+        .set_paper_content(&mut tx, id, &content_hash, &content_text)
+        .await?;
+    state
+        .db
+        .insert_audit_log(
+            &mut tx,
+            &auth.username,
+            AuditAction::ExtractPages,
+            id,
+            &audit::diff(
+                &serde_json::Value::Null,
+                &serde_json::json!({
+                    "extracted_from": body.id,
+                    "pages": body.pages.iter().map(|page| page.page).collect::<Vec<u32>>(),
+                }),
+            ),
+        )
+        .await?;
+
+    let filepath = state.env_vars.paths.get_path_from_slug(&filelink_slug);
+    if let Err(e) = fs::write(&filepath, file_data).await {
+        tracing::error!("Error writing file: {}", e);
+
+        tx.rollback().await?;
+        return Ok(BackendResponse::error(
+            "Error writing question paper file.".into(),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    if let Err(e) = tx.commit().await {
+        fs::remove_file(filepath).await?;
+        return Err(e.into());
+    }
+
+    let new_paper = state.db.get_paper_by_id(id).await?;
+
+    Ok(BackendResponse::ok(
+        format!("Successfully extracted the pages into paper {}.", id),
+        new_paper.with_url(&state.env_vars)?,
+    ))
+}
+
+/// Fetches the roles of all admins.
+pub async fn get_roles(State(state): HandlerState) -> HandlerReturn<Vec<AdminRole>> {
+    let roles = state.db.get_admin_roles().await?;
+
+    Ok(BackendResponse::ok(
+        format!("Successfully fetched {} roles.", roles.len()),
+        roles,
+    ))
+}
+
+#[derive(Deserialize)]
+/// The request format for the set role endpoint
+pub struct SetRoleReq {
+    /// The Github username of the admin
+    username: String,
+    /// One of `viewer`, `reviewer`, `maintainer`, or `owner`
+    role: String,
+}
+
+/// Sets the role of an admin. The new role is used from the admin's next request. Owners cannot change their own role.
+///
+/// Request format - [`SetRoleReq`]
+pub async fn set_role(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    Json(body): Json<SetRoleReq>,
+) -> HandlerReturn<()> {
+    let Ok(role) = Role::try_from(body.role.as_str()) else {
+        return Ok(BackendResponse::error(
+            "Error: Invalid role.".into(),
+            StatusCode::BAD_REQUEST,
+        ));
+    };
+
+    if body.username == auth.username {
+        return Ok(BackendResponse::error(
+            "Error: You cannot change your own role.".into(),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    state
+        .db
+        .set_admin_role(&body.username, role, &auth.username)
+        .await?;
+
+    Ok(BackendResponse::ok(
+        format!("Successfully set the role of {}.", body.username),
+        (),
+    ))
+}
+
+/// Gets all the courses in the course registry, ordered by code.
+///
+/// Optionally filtered by the `department` URL parameter (a department code, see [`departments`]).
+pub async fn get_courses(
+    State(state): HandlerState,
+    Query(params): Query<HashMap<String, String>>,
+) -> HandlerReturn<Vec<Course>> {
+    let department_filter = params
+        .get("department")
+        .map(|value| departments::normalize_department_code(value))
+        .filter(|code| !code.is_empty());
+    let courses = state.db.get_courses(department_filter.as_deref()).await?;
+
+    Ok(BackendResponse::ok(
+        format!("Successfully fetched {} courses.", courses.len()),
+        courses,
+    ))
+}
+
+/// Adds a course to the course registry, or updates it if the course code is already registered. The course code is normalized (see [`courses::normalize_course_code`]).
+///
+/// Papers already in the database are not changed, their course names are updated from the registry when they are edited.
+///
+/// Request format - [`Course`]
+pub async fn upsert_course(
+    State(state): HandlerState,
+    Json(body): Json<Course>,
+) -> HandlerReturn<Course> {
+    let course = match body.normalize() {
+        Ok(course) => course,
+        Err(e) => {
+            return Ok(BackendResponse::error(
+                format!("Error: {}", e),
+                StatusCode::BAD_REQUEST,
+            ))
+        }
+    };
+
+    state
+        .db
+        .upsert_courses(std::slice::from_ref(&course))
+        .await?;
+
+    Ok(BackendResponse::ok(
+        "Successfully saved the course.".into(),
+        course,
+    ))
+}
+
+#[derive(Deserialize)]
+/// The request format for the delete course endpoint
+pub struct DeleteCourseReq {
+    code: String,
+}
+
+/// Removes a course from the course registry.
+///
+/// Request format - [`DeleteCourseReq`]
+pub async fn delete_course(
+    State(state): HandlerState,
+    Json(body): Json<DeleteCourseReq>,
+) -> HandlerReturn<()> {
+    if state
+        .db
+        .delete_course(&courses::normalize_course_code(&body.code))
+        .await?
+    {
+        Ok(BackendResponse::ok(
+            "Successfully deleted the course.".into(),
+            (),
+        ))
+    } else {
+        Ok(BackendResponse::error(
+            "Error: Course not found.".into(),
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
+
+#[derive(Serialize)]
+/// The response format for the course import endpoint
+pub struct ImportCoursesRes {
+    /// The number of courses added or updated
+    count: usize,
+    /// The error of each invalid row, if the import failed
+    errors: Vec<String>,
+}
+
+/// Imports courses into the course registry from a CSV file (sent as the request body). Courses that are already registered are updated. See [`courses::parse_csv`] for the format.
+///
+/// Either all the courses are imported or none are. If any row is invalid, the error of every invalid row is sent in the response data.
+pub async fn import_courses(
+    State(state): HandlerState,
+    body: String,
+) -> HandlerReturn<ImportCoursesRes> {
+    let courses = match courses::parse_csv(&body) {
+        Ok(courses) => courses,
+        Err(errors) => {
+            return Ok(BackendResponse::error_with_data(
+                "Error: The CSV file has invalid rows. No courses were imported.".into(),
+                ImportCoursesRes { count: 0, errors },
+                StatusCode::BAD_REQUEST,
+            ))
+        }
+    };
+
+    state.db.upsert_courses(&courses).await?;
+
+    Ok(BackendResponse::ok(
+        format!("Successfully imported {} courses.", courses.len()),
+        ImportCoursesRes {
+            count: courses.len(),
+            errors: Vec::new(),
+        },
+    ))
+}
+
+/// Gets all the tags and the number of published papers with each tag, ordered by name.
+pub async fn get_tags(State(state): HandlerState) -> HandlerReturn<Vec<Tag>> {
+    let tags = state.db.get_tags().await?;
+
+    Ok(BackendResponse::ok(
+        format!("Successfully fetched {} tags.", tags.len()),
+        tags,
+    ))
+}
+
+#[derive(Deserialize)]
+/// The request format for the create tag endpoint
+pub struct CreateTagReq {
+    name: String,
+    #[serde(default)]
+    description: String,
+}
+
+/// Checks a (normalized) tag name. Returns an error message if the name is empty or too long.
+fn check_tag_name(name: &str) -> Option<String> {
+    if name.is_empty() {
+        Some("Error: The tag name cannot be empty.".into())
+    } else if name.chars().count() > tags::MAX_TAG_NAME_CHARS {
+        Some(format!(
+            "Error: Tag names can be at most {} characters long.",
+            tags::MAX_TAG_NAME_CHARS
+        ))
+    } else {
+        None
+    }
+}
+
+/// Creates a new tag. The name is normalized (see [`tags::normalize_tag_name`]) and must be unique.
+///
+/// Request format - [`CreateTagReq`]
+pub async fn create_tag(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    Json(body): Json<CreateTagReq>,
+) -> HandlerReturn<Tag> {
+    let name = tags::normalize_tag_name(&body.name);
+    if let Some(message) = check_tag_name(&name) {
+        return Ok(BackendResponse::error(message, StatusCode::BAD_REQUEST));
+    }
+
+    let Some(id) = state
+        .db
+        .insert_tag(&name, body.description.trim(), &auth.username)
+        .await?
+    else {
+        return Ok(BackendResponse::error(
+            format!("Error: The tag `{}` already exists.", name),
+            StatusCode::CONFLICT,
+        ));
+    };
+
+    let tag = state
+        .db
+        .get_tag(id)
+        .await?
+        .wrap_err("Error: The created tag was not found.")?;
+
+    Ok(BackendResponse::ok(
+        "Successfully created the tag.".into(),
+        tag,
+    ))
+}
+
+#[derive(Deserialize)]
+/// The request format for the update tag endpoint. Fields that are not set are not changed.
+pub struct UpdateTagReq {
+    id: i32,
+    name: Option<String>,
+    description: Option<String>,
+}
+
+/// Renames a tag or changes its description. Renaming a tag is recorded in the audit log for every paper with the tag.
+///
+/// Request format - [`UpdateTagReq`]
+pub async fn update_tag(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    Json(body): Json<UpdateTagReq>,
+) -> HandlerReturn<Tag> {
+    let Some(tag) = state.db.get_tag(body.id).await? else {
+        return Ok(BackendResponse::error(
+            "Error: Tag not found.".into(),
+            StatusCode::NOT_FOUND,
+        ));
+    };
+
+    let name = body
+        .name
+        .map(|name| tags::normalize_tag_name(&name))
+        .unwrap_or(tag.name.clone());
+    if let Some(message) = check_tag_name(&name) {
+        return Ok(BackendResponse::error(message, StatusCode::BAD_REQUEST));
+    }
+    if name != tag.name
+        && state
+            .db
+            .get_unknown_tags(std::slice::from_ref(&name))
+            .await?
+            .is_empty()
+    {
+        return Ok(BackendResponse::error(
+            format!("Error: The tag `{}` already exists.", name),
+            StatusCode::CONFLICT,
+        ));
+    }
+
+    let description = body
+        .description
+        .map(|description| description.trim().to_owned())
+        .unwrap_or(tag.description);
+
+    state
+        .db
+        .update_tag(body.id, &name, &description, &auth.username)
+        .await?;
+
+    let tag = state
+        .db
+        .get_tag(body.id)
+        .await?
+        .wrap_err("Error: The updated tag was not found.")?;
+
+    Ok(BackendResponse::ok(
+        "Successfully updated the tag.".into(),
+        tag,
+    ))
+}
+
+#[derive(Deserialize)]
+/// The request format for the delete tag endpoint
+pub struct DeleteTagReq {
+    id: i32,
+}
+
+/// Deletes a tag and removes it from all papers. The removal is recorded in the audit log for every paper with the tag.
+///
+/// Request format - [`DeleteTagReq`]
+pub async fn delete_tag(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    Json(body): Json<DeleteTagReq>,
+) -> HandlerReturn<()> {
+    if state.db.delete_tag(body.id, &auth.username).await? {
+        Ok(BackendResponse::ok(
+            "Successfully deleted the tag.".into(),
+            (),
+        ))
+    } else {
+        Ok(BackendResponse::error(
+            "Error: Tag not found.".into(),
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+/// The details for an uploaded question paper file
+pub struct FileDetails {
+    pub course_code: String,
+    pub course_name: String,
+    pub year: i32,
+    pub exam: String,
+    pub semester: String,
+    pub filename: String,
+    pub note: String,
+    #[serde(default)]
+    /// The filenames of the uploaded files that make up this paper, in order. They are merged into a single PDF. If empty, the paper is the single file named `filename`.
+    pub files: Vec<String>,
+    #[serde(default)]
+    /// The id of the paper this file is attached to, if the file is an attachment (eg: solutions) and not a question paper
+    pub attachment_of: Option<i32>,
+    #[serde(default)]
+    /// The kind of attachment (see [`AttachmentKind`]), required if `attachment_of` is set
+    pub attachment_kind: Option<String>,
+}
+
+impl FileDetails {
+    /// Returns the filenames of the uploaded files that make up this paper, in order
+    pub fn part_filenames(&self) -> Vec<String> {
+        if self.files.is_empty() {
+            vec![self.filename.clone()]
+        } else {
+            self.files.clone()
+        }
+    }
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+/// The status of an uploaded question paper file
+pub struct UploadStatus {
+    /// The filename
+    pub filename: String,
+    /// The state of the file: `pending`, `processing`, `success`, `error`, or `rejected` (by a reviewer after the upload)
+    pub status: String,
+    /// A message describing the status
+    pub message: String,
+    /// The id of the uploaded paper, if the upload succeeded
+    pub paper_id: Option<i32>,
+}
+
+impl UploadStatus {
+    pub fn pending(filename: String) -> Self {
+        Self {
+            filename,
+            status: "pending".into(),
+            message: "Waiting to be processed.".into(),
+            paper_id: None,
+        }
+    }
+
+    pub fn processing(filename: String) -> Self {
+        Self {
+            filename,
+            status: "processing".into(),
+            message: "Processing the paper.".into(),
+            paper_id: None,
+        }
+    }
+
+    pub fn ok(filename: String, paper_id: i32) -> Self {
+        Self {
+            filename,
+            status: "success".into(),
+            message: "Successfully uploaded paper.".into(),
+            paper_id: Some(paper_id),
+        }
+    }
+
+    pub fn error(filename: String, message: String) -> Self {
+        Self {
+            filename,
+            status: "error".into(),
+            message,
+            paper_id: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+/// The response format for the upload endpoint
+pub struct UploadRes {
+    /// The token of the upload job processing the papers, used to check its progress
+    job_token: String,
+}
+
+/// Uploads question papers to the server
+///
+/// The files are only staged here and the response (`202 Accepted`) contains the token of an upload job. The papers are validated, scanned, and inserted by the upload worker in the background (see [`super::workers::upload_worker`]). The progress can be checked using the [`get_upload_job`] endpoint.
+///
+/// Request format - Multipart form with a `file_details` field of the format [`FileDetails`] and one `files` field per file. Each file is matched to its details using the multipart filename, which must be equal to the `filename` in the details (or one of the `files` for papers made of multiple files).
+///
+/// # Request Query Parameters
+/// * `atomic` (optional): If `true`, either all the papers are uploaded or none are. Every file is validated first, all papers are inserted in a single transaction, and if any step fails, everything is rolled back and the files already written are removed.
+pub async fn upload(
+    State(state): HandlerState,
+    Query(params): Query<HashMap<String, String>>,
+    mut multipart: Multipart,
+) -> HandlerReturn<UploadRes> {
+    let atomic = params.get("atomic").is_some_and(|value| value == "true");
+
+    let mut files = Vec::<UploadedFile>::new();
+    let mut file_details: String = "".into();
+
+    while let Some(field) = multipart.next_field().await? {
+        let name = field
+            .name()
+            .ok_or(eyre!("Error parsing file upload multipart field."))?
+            .to_string();
+
+        if name == "files" {
+            files.push((
+                field.file_name().map(|name| name.to_owned()),
+                field.headers().clone(),
+                field.bytes().await?,
+            ));
+        } else if name == "file_details" {
+            if file_details.is_empty() {
+                file_details = field.text().await?;
+            } else {
+                return Ok(BackendResponse::error(
+                    "Error: Multiple `file_details` fields found.".into(),
+                    StatusCode::BAD_REQUEST,
+                ));
+            }
+        }
+    }
+
+    let files = files;
+    let file_details: Vec<FileDetails> = serde_json::from_str(&file_details)?;
+
+    if files.len() > state.env_vars.max_upload_limit {
+        return Ok(BackendResponse::error(
+            format!(
+                "Only upto {} files can be uploaded. Found {}.",
+                state.env_vars.max_upload_limit,
+                files.len()
+            ),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let (matched_uploads, mut match_errors) = match_files(files, file_details);
+
+    // Attachments must belong to an existing paper
+    let mut uploads = Vec::<Upload>::new();
+    for (files, details) in matched_uploads {
+        if let Some(message) = check_attachment(&state, &details).await {
+            match_errors.push(UploadStatus::error(details.filename, message));
+        } else {
+            uploads.push((files, details));
+        }
+    }
+
+    // Nothing is uploaded in atomic mode if any file could not be matched
+    let (uploads, match_errors) = if atomic && !match_errors.is_empty() {
+        let not_uploaded = uploads.into_iter().map(|(_, details)| {
+            UploadStatus::error(
+                details.filename,
+                "Not uploaded: Other files in the batch failed validation.".into(),
+            )
+        });
+
+        (Vec::new(), not_uploaded.chain(match_errors).collect())
+    } else {
+        (uploads, match_errors)
+    };
+
+    // Stage the files and create the job
+    let mut tx = state.db.begin_transaction().await?;
+    let (job_id, job_token) = state.db.insert_upload_job(&mut tx, atomic).await?;
+    let mut staged_paths = Vec::<PathBuf>::new();
+
+    let result: Result<()> = async {
+        for (index, (files, details)) in uploads.into_iter().enumerate() {
+            let mut content_types = Vec::<String>::new();
+            let mut staged_files = Vec::<String>::new();
+
+            for (part, (file_headers, file_data)) in files.into_iter().enumerate() {
+                content_types.push(
+                    file_headers
+                        .get("content-type")
+                        .and_then(|value| value.to_str().ok())
+                        .unwrap_or("")
+                        .to_owned(),
+                );
+
+                let staged_file = format!("{}_{}_{}", job_id, index, part);
+                let staged_path = state.env_vars.paths.get_staging_path(&staged_file);
+                fs::write(&staged_path, file_data).await?;
+                staged_paths.push(staged_path);
+                staged_files.push(staged_file);
+            }
+
+            state
+                .db
+                .insert_upload_job_file(
+                    &mut tx,
+                    job_id,
+                    Some(&details),
+                    &content_types,
+                    &staged_files,
+                    &UploadStatus::pending(details.filename.clone()),
+                )
+                .await?;
+        }
+
+        for status in match_errors {
+            state
+                .db
+                .insert_upload_job_file(&mut tx, job_id, None, &[], &[], &status)
+                .await?;
+        }
+
+        Ok(())
+    }
+    .await;
+
+    let result = match result {
+        Ok(()) => tx.commit().await.map_err(color_eyre::eyre::Error::from),
+        Err(e) => {
+            tx.rollback().await?;
+            Err(e)
+        }
+    };
+
+    if let Err(e) = result {
+        for staged_path in staged_paths {
+            let _ = fs::remove_file(staged_path).await;
+        }
+
+        return Err(e.into());
+    }
+
+    state.upload_notify.notify_one();
+
+    Ok(BackendResponse::accepted(
+        "Successfully received the papers. They will be processed shortly.".into(),
+        UploadRes { job_token },
+    ))
+}
+
+/// A file from the multipart form, its filename, headers, and data.
+type UploadedFile = (Option<String>, HeaderMap, Bytes);
+
+/// A paper to be uploaded, the headers and data of each of its files (in order), and its details.
+type Upload = (Vec<(HeaderMap, Bytes)>, FileDetails);
+
+/// Checks the attachment fields of an uploaded file's details. Returns an error message if the file is an attachment of a paper that does not exist, is deleted, or is an attachment itself, or if the attachment kind is missing or invalid.
+async fn check_attachment(state: &RouterState, details: &FileDetails) -> Option<String> {
+    match (details.attachment_of, &details.attachment_kind) {
+        (None, None) => None,
+        (Some(paper_id), Some(kind)) => {
+            if AttachmentKind::try_from(kind.as_str()).is_err() {
+                return Some("Error: Invalid attachment kind.".into());
+            }
+
+            match state.db.get_paper_by_id(paper_id).await {
+                Ok(paper) if !paper.is_deleted && paper.attachment_of.is_none() => None,
+                Ok(paper) if paper.attachment_of.is_some() => Some(format!(
+                    "Error: Paper {} is an attachment, files can not be attached to it.",
+                    paper_id
+                )),
+                _ => Some(format!(
+                    "Error: Paper {} does not exist or is deleted.",
+                    paper_id
+                )),
+            }
+        }
+        (Some(_), None) => Some("Error: `attachment_kind` is required for attachments.".into()),
+        (None, Some(_)) => Some("Error: `attachment_of` is required for attachments.".into()),
+    }
+}
+
+/// Pairs each uploaded file with its [`FileDetails`] entry using the multipart filename. A details entry can reference multiple files (see [`FileDetails::files`]).
+///
+/// Returns the matched uploads (in the order of the details) and an error status for every file or details entry that could not be matched (missing, duplicated, or extra).
+fn match_files(
+    files: Vec<UploadedFile>,
+    file_details: Vec<FileDetails>,
+) -> (Vec<Upload>, Vec<UploadStatus>) {
+    let mut errors = Vec::<UploadStatus>::new();
+
+    // Index the files by filename, files with a repeated filename can not be matched
+    let mut files_by_name = HashMap::<String, (HeaderMap, Bytes)>::new();
+    let mut duplicate_files = HashSet::<String>::new();
+    for (filename, file_headers, file_data) in files {
+        let Some(filename) = filename else {
+            errors.push(UploadStatus::error(
+                "".into(),
+                "Error: A file was uploaded without a filename.".into(),
+            ));
+            continue;
+        };
+
+        if duplicate_files.contains(&filename) || files_by_name.contains_key(&filename) {
+            if files_by_name.remove(&filename).is_some() {
+                errors.push(UploadStatus::error(
+                    filename.clone(),
+                    "Error: Multiple files were uploaded with this filename.".into(),
+                ));
+            }
+            duplicate_files.insert(filename.clone());
+            errors.push(UploadStatus::error(
+                filename,
+                "Error: Multiple files were uploaded with this filename.".into(),
+            ));
+            continue;
+        }
+
+        files_by_name.insert(filename, (file_headers, file_data));
+    }
+
+    // Number of details entries referencing each file
+    let mut reference_counts = HashMap::<String, usize>::new();
+    for details in &file_details {
+        for part in details.part_filenames() {
+            *reference_counts.entry(part).or_default() += 1;
+        }
+    }
+
+    let mut uploads = Vec::<Upload>::new();
+    for details in file_details {
+        let parts = details.part_filenames();
+
+        if parts
+            .iter()
+            .any(|part| reference_counts.get(part).is_some_and(|&count| count > 1))
+        {
+            // The files are not uploaded since it is ambiguous which details they belong to
+            for part in &parts {
+                files_by_name.remove(part);
+            }
+            errors.push(UploadStatus::error(
+                details.filename,
+                "Error: Multiple file details entries were found for the same file.".into(),
+            ));
+        } else if parts.iter().any(|part| duplicate_files.contains(part)) {
+            // The error has already been reported for the files, the other parts are not uploaded either
+            for part in &parts {
+                files_by_name.remove(part);
+            }
+            if parts.len() > 1 {
+                errors.push(UploadStatus::error(
+                    details.filename,
+                    "Error: Multiple files were uploaded with the same filename as one of the parts of this paper.".into(),
+                ));
+            }
+        } else if let Some(missing) = parts.iter().find(|part| !files_by_name.contains_key(*part)) {
+            let message = if parts.len() > 1 {
+                format!("Error: No file was uploaded for the part `{}`.", missing)
+            } else {
+                "Error: No file was uploaded for these file details.".into()
+            };
+            for part in &parts {
+                files_by_name.remove(part);
+            }
+            errors.push(UploadStatus::error(details.filename, message));
+        } else {
+            let files = parts
+                .iter()
+                .filter_map(|part| files_by_name.remove(part))
+                .collect();
+            uploads.push((files, details));
+        }
+    }
+
+    // Files left over have no details
+    for filename in files_by_name.into_keys() {
+        errors.push(UploadStatus::error(
+            filename,
+            "Error: No file details were found for this file.".into(),
+        ));
+    }
+
+    (uploads, errors)
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+/// An upload job and the status of each of its files
+pub struct UploadJob {
+    #[serde(skip)]
+    pub id: i32,
+    /// The unguessable token identifying the job publicly. Job ids are sequential and are never exposed.
+    pub token: String,
+    /// Whether the job uploads all the papers or none (see [`upload`])
+    pub atomic: bool,
+    /// The state of the job: `pending`, `processing`, or `done`
+    pub status: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub finished_at: Option<chrono::NaiveDateTime>,
+    #[sqlx(skip)]
+    /// The status of each file in the job
+    pub files: Vec<UploadStatus>,
+}
+
+/// Gets the progress of an upload job and the status of each of its files.
+///
+/// # Request Path Parameters
+/// * `token`: The token of the upload job (returned by the [`upload`] endpoint)
+pub async fn get_upload_job(
+    State(state): HandlerState,
+    Path(token): Path<String>,
+) -> HandlerReturn<UploadJob> {
+    if let Some(job) = state.db.get_upload_job(&token).await? {
+        Ok(BackendResponse::ok(
+            format!("Upload job is {}.", job.status),
+            job,
+        ))
+    } else {
+        Ok(BackendResponse::error(
+            "Error: Upload job not found.".into(),
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
+
+#[derive(Deserialize)]
+/// The request format for the delete endpoint
+pub struct DeleteReq {
+    id: i32,
+}
+
+/// (Soft) Deletes a given paper. Papers already in the trash can not be deleted again (see [`hard_delete`] to delete them permanently). The paper's attachments are moved to the trash along with it.
+///
+/// Request format - [`DeleteReq`]
+pub async fn delete(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    Json(body): Json<DeleteReq>,
+) -> HandlerReturn<()> {
+    let paper_deleted = state.db.soft_delete(body.id, &auth.username).await?;
+
+    if paper_deleted {
+        Ok(BackendResponse::ok(
+            "Succesfully deleted the paper.".into(),
+            (),
+        ))
+    } else if state
+        .db
+        .get_paper_by_id(body.id)
+        .await
+        .is_ok_and(|paper| paper.is_deleted)
+    {
+        Ok(BackendResponse::error(
+            "Error: The paper is already deleted.".into(),
+            StatusCode::CONFLICT,
+        ))
+    } else {
+        Ok(BackendResponse::error(
+            "Error: The paper does not exist.".into(),
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
+
+#[derive(Deserialize)]
+/// The request format for the reject endpoint
+pub struct RejectReq {
+    id: i32,
+    /// One of `duplicate`, `wrong_paper`, `illegible`, `not_a_question_paper`, or `other`
+    reason: String,
+    #[serde(default)]
+    /// An explanation of the rejection (required if the reason is `other`)
+    note: String,
+}
+
+/// Rejects an unapproved paper with a reason and moves it to the trash. The reason is shown in the trash and to the uploader.
+///
+/// Request format - [`RejectReq`]
+pub async fn reject(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    Json(body): Json<RejectReq>,
+) -> HandlerReturn<()> {
+    let Ok(reason) = RejectReason::try_from(body.reason.as_str()) else {
+        return Ok(BackendResponse::error(
+            "Error: Invalid reject reason.".into(),
+            StatusCode::BAD_REQUEST,
+        ));
+    };
+
+    let note = body.note.trim();
+    if matches!(reason, RejectReason::Other) && note.is_empty() {
+        return Ok(BackendResponse::error(
+            "Error: A note is required when the reason is `other`.".into(),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    if state
+        .db
+        .reject_paper(body.id, &auth.username, &reason, note)
+        .await?
+    {
+        Ok(BackendResponse::ok(
+            "Successfully rejected the paper.".into(),
+            (),
+        ))
+    } else {
+        Ok(BackendResponse::error(
+            "No paper was rejected. Either the paper does not exist, is approved, or is already deleted.".into(),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+#[derive(Deserialize)]
+/// The request format for the hard delete endpoint
+pub struct HardDeleteReq {
+    ids: Vec<i32>,
+}
+
+#[derive(Serialize)]
+/// The status of a paper to be deleted
+pub struct DeleteStatus {
+    id: i32,
+    status: &'static str,
+    message: &'static str,
+}
+
+impl DeleteStatus {
+    fn ok(id: i32) -> Self {
+        Self {
+            id,
+            status: "success",
+            message: "Successfully hard deleted the paper.",
+        }
+    }
+
+    fn error(id: i32, message: &'static str) -> Self {
+        Self {
+            id,
+            status: "error",
+            message,
+        }
+    }
+
+    /// Whether the paper was deleted
+    pub(super) fn is_ok(&self) -> bool {
+        self.status == "success"
+    }
+}
+
+/// Hard deletes papers from a list of ids. Only papers in the trash can be hard deleted, and papers with attachments can only be hard deleted after their attachments.
+///
+/// Request format - [`HardDeleteReq`]
+pub async fn hard_delete(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+    Json(body): Json<HardDeleteReq>,
+) -> HandlerReturn<Vec<DeleteStatus>> {
+    let mut delete_statuses = Vec::<DeleteStatus>::new();
+    let mut deleted_count = 0;
+    for id in body.ids {
+        if let Ok(paper) = state.db.get_paper_by_id(id).await {
+            let delete_status = hard_delete_paper(&state, &paper, &auth.username).await?;
+            if delete_status.is_ok() {
+                deleted_count += 1;
+            }
+
+            delete_statuses.push(delete_status);
+        }
+    }
+
+    let message = if deleted_count > 0 {
+        format!("Successfully hard deleted {} papers.", deleted_count)
+    } else {
+        "No papers were deleted.".into()
+    };
+
+    Ok(BackendResponse::ok(message, delete_statuses))
+}
+
+/// Permanently deletes a paper in the trash from the database and removes its file, along with its previous files (see [`crate::db::Database::get_old_filelinks`]). Also used by the trash purge worker.
+///
+/// The files are first moved aside, and only removed once the database transaction is committed. If anything fails, the files are moved back so that the paper (and its history) is left unchanged.
+pub(super) async fn hard_delete_paper(
+    state: &RouterState,
+    paper: &AdminDashboardQP,
+    username: &str,
+) -> Result<DeleteStatus> {
+    let id = paper.qp.id;
+    if !paper.is_deleted {
+        return Ok(DeleteStatus::error(
+            id,
+            "Only papers in the trash can be hard deleted.",
+        ));
+    }
+
+    if state.db.has_attachments(id).await? {
+        return Ok(DeleteStatus::error(
+            id,
+            "The paper has attachments. Hard delete its attachments first.",
+        ));
+    }
+
+    let old_filelinks = state.db.get_old_filelinks(id).await?;
+    let Some(tx) = state.db.hard_delete(id, username).await? else {
+        return Ok(DeleteStatus::error(
+            id,
+            "The paper does not exist, is not in the trash, or has attachments.",
+        ));
+    };
+
+    // The current file must exist, the previous files may have already been removed (e.g. renamed by an edit)
+    let filepaths =
+        [(paper.get_system_path(&state.env_vars), true)]
+            .into_iter()
+            .chain(old_filelinks.iter().map(|old_filelink| {
+                (state.env_vars.paths.get_path_from_slug(old_filelink), false)
+            }));
+
+    // The moved files, along with their original paths
+    let mut moved_files = Vec::<(PathBuf, PathBuf)>::new();
+    let mut move_failed = false;
+    for (filepath, required) in filepaths {
+        let mut aside_path = filepath.clone().into_os_string();
+        aside_path.push(".deleted");
+        let aside_path = PathBuf::from(aside_path);
+
+        match fs::rename(&filepath, &aside_path).await {
+            Ok(()) => moved_files.push((filepath, aside_path)),
+            Err(e) if !required && e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                tracing::error!(
+                    "Error moving file {} of paper {}: {}",
+                    filepath.to_string_lossy(),
+                    id,
+                    e
+                );
+                move_failed = true;
+                break;
+            }
+        }
+    }
+
+    let commit_result = if move_failed {
+        tx.rollback().await?;
+        Err("Failed to delete file.")
+    } else if let Err(e) = tx.commit().await {
+        tracing::error!("Error committing the hard delete of paper {}: {}", id, e);
+        Err("Error committing the transaction.")
+    } else {
+        Ok(())
+    };
+
+    match commit_result {
+        Ok(()) => {
+            for (_, aside_path) in moved_files {
+                if let Err(e) = fs::remove_file(&aside_path).await {
+                    tracing::error!(
+                        "Error removing file {}: {}",
+                        aside_path.to_string_lossy(),
+                        e
+                    );
+                }
+            }
+
+            Ok(DeleteStatus::ok(id))
+        }
+        Err(message) => {
+            for (filepath, aside_path) in moved_files {
+                fs::rename(&aside_path, &filepath).await?;
+            }
+
+            Ok(DeleteStatus::error(id, message))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+/// The request format for the restore endpoint
+pub struct RestoreReq {
+    ids: Vec<i32>,
+}
+
+#[derive(Serialize)]
+/// The status of a paper to be restored
+pub struct RestoreStatus {
+    id: i32,
+    status: &'static str,
+    message: &'static str,
+}
+
+impl RestoreStatus {
+    fn ok(id: i32) -> Self {
+        Self {
             id,
             status: "success",
-            message: "Successfully hard deleted the paper.",
+            message: "Successfully restored the paper.",
         }
     }
 
@@ -510,41 +2507,333 @@ impl DeleteStatus {
     }
 }
 
-/// Hard deletes papers from a list of ids.
+/// Restores soft-deleted papers (including papers deleted by being replaced in an edit) from a list of ids. The restored papers are sent back to the unapproved papers.
 ///
-/// Request format - [`HardDeleteReq`]
-pub async fn hard_delete(
+/// Request format - [`RestoreReq`]
+pub async fn restore(
+    Extension(auth): Extension<Auth>,
     State(state): HandlerState,
-    Json(body): Json<HardDeleteReq>,
-) -> HandlerReturn<Vec<DeleteStatus>> {
-    let mut delete_statuses = Vec::<DeleteStatus>::new();
-    let mut deleted_count = 0;
+    Json(body): Json<RestoreReq>,
+) -> HandlerReturn<Vec<RestoreStatus>> {
+    let mut restore_statuses = Vec::<RestoreStatus>::new();
+    let mut restored_count = 0;
     for id in body.ids {
-        if let Ok(paper) = state.db.get_paper_by_id(id).await {
-            let tx = state.db.hard_delete(id).await?;
-            let filepath = state.env_vars.paths.get_path_from_slug(&paper.qp.filelink);
-            if fs::remove_file(&filepath).await.is_ok() {
-                if tx.commit().await.is_ok() {
-                    delete_statuses.push(DeleteStatus::ok(id));
-                    deleted_count += 1;
-                } else {
-                    delete_statuses
-                        .push(DeleteStatus::error(id, "Error committing the transaction."));
-                }
-            } else {
+        let Some((tx, old_filelink, restored_qp)) = state
+            .db
+            .restore_paper(id, &auth.username, &state.env_vars)
+            .await?
+        else {
+            restore_statuses.push(RestoreStatus::error(
+                id,
+                "The paper does not exist or is not deleted.",
+            ));
+            continue;
+        };
+
+        let old_filepath = state.env_vars.paths.get_path_from_slug(&old_filelink);
+        let new_filepath = state
+            .env_vars
+            .paths
+            .get_path_from_slug(&restored_qp.qp.filelink);
+
+        if old_filepath != new_filepath {
+            if let Err(e) = fs::copy(old_filepath, new_filepath).await {
+                tracing::error!("Error copying file: {}", e);
+
                 tx.rollback().await?;
-                delete_statuses.push(DeleteStatus::error(id, "Failed to delete file."));
+                restore_statuses.push(RestoreStatus::error(
+                    id,
+                    "Error copying question paper file.",
+                ));
+                continue;
             }
         }
+
+        if tx.commit().await.is_ok() {
+            restore_statuses.push(RestoreStatus::ok(id));
+            restored_count += 1;
+        } else {
+            restore_statuses.push(RestoreStatus::error(
+                id,
+                "Error committing the transaction.",
+            ));
+        }
     }
 
-    let message = if deleted_count > 0 {
-        format!("Successfully hard deleted {} papers.", deleted_count)
+    let message = if restored_count > 0 {
+        format!("Successfully restored {} papers.", restored_count)
     } else {
-        "No papers were deleted.".into()
+        "No papers were restored.".into()
     };
 
-    Ok(BackendResponse::ok(message, delete_statuses))
+    Ok(BackendResponse::ok(message, restore_statuses))
+}
+
+/// Maximum number of audit log entries returned by the audit log endpoint
+const AUDIT_LOG_LIMIT: i64 = 500;
+
+/// Parses a timestamp filter. Accepts a datetime (`2024-01-01T10:00:00`) or a date (`2024-01-01`). A date is taken as the start of the day if `end_of_day` is false, and the end of the day otherwise.
+fn parse_timestamp(value: &str, end_of_day: bool) -> Option<NaiveDateTime> {
+    if let Ok(timestamp) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S") {
+        Some(timestamp)
+    } else {
+        let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+        if end_of_day {
+            date.and_hms_opt(23, 59, 59)
+        } else {
+            date.and_hms_opt(0, 0, 0)
+        }
+    }
+}
+
+/// Fetches the latest entries of the audit log of admin actions.
+///
+/// Optional query parameters:
+/// - `paper_id`: Only show actions taken on this paper
+/// - `username`: Only show actions taken by this admin
+/// - `from`: Only show actions taken on or after this time (`YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS`)
+/// - `to`: Only show actions taken on or before this time (`YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS`)
+pub async fn get_audit_log(
+    State(state): HandlerState,
+    Query(params): Query<HashMap<String, String>>,
+) -> HandlerReturn<Vec<AuditLogEntry>> {
+    let paper_id = match params.get("paper_id").map(|id| id.parse::<i32>()) {
+        Some(Ok(id)) => Some(id),
+        Some(Err(_)) => {
+            return Ok(BackendResponse::error(
+                "Invalid paper id.".into(),
+                StatusCode::BAD_REQUEST,
+            ))
+        }
+        None => None,
+    };
+
+    let mut timestamps = [None, None];
+    for (timestamp, (key, end_of_day)) in timestamps.iter_mut().zip([("from", false), ("to", true)])
+    {
+        if let Some(value) = params.get(key) {
+            *timestamp = parse_timestamp(value, end_of_day);
+            if timestamp.is_none() {
+                return Ok(BackendResponse::error(
+                    format!("Invalid `{}` timestamp.", key),
+                    StatusCode::BAD_REQUEST,
+                ));
+            }
+        }
+    }
+    let [from, to] = timestamps;
+
+    let entries = state
+        .db
+        .get_audit_log(paper_id, params.get("username"), from, to, AUDIT_LOG_LIMIT)
+        .await?;
+
+    Ok(BackendResponse::ok(
+        format!("Successfully fetched {} audit log entries.", entries.len()),
+        entries,
+    ))
+}
+
+/// Default number of periods in the timeline of the stats endpoint
+const DEFAULT_STATS_PERIODS: i64 = 30;
+/// Maximum number of periods in the timeline of the stats endpoint
+const MAX_STATS_PERIODS: i64 = 366;
+
+/// Fetches statistics about the archive and the review queue: the uploads, approvals, and queue size over time, the median time taken to approve a paper, the approvals by each reviewer, and the number of published papers by source, exam, year, and department.
+///
+/// Optional query parameters:
+/// - `period`: The length of the periods in the timeline, `day` (default) or `week`
+/// - `from`: The start of the timeline (`YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS`). Defaults to 30 periods before `to`.
+/// - `to`: The end of the timeline (`YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS`). Defaults to now.
+pub async fn get_stats(
+    State(state): HandlerState,
+    Query(params): Query<HashMap<String, String>>,
+) -> HandlerReturn<Stats> {
+    let period = match params
+        .get("period")
+        .map(|period| StatsPeriod::try_from(period.as_str()))
+    {
+        Some(Ok(period)) => period,
+        Some(Err(_)) => {
+            return Ok(BackendResponse::error(
+                "Invalid `period`. Must be `day` or `week`.".into(),
+                StatusCode::BAD_REQUEST,
+            ))
+        }
+        None => StatsPeriod::Day,
+    };
+
+    let to = match params.get("to").map(|value| parse_timestamp(value, true)) {
+        Some(Some(to)) => to,
+        Some(None) => {
+            return Ok(BackendResponse::error(
+                "Invalid `to` timestamp.".into(),
+                StatusCode::BAD_REQUEST,
+            ))
+        }
+        None => chrono::Utc::now().naive_utc(),
+    };
+    let from = match params
+        .get("from")
+        .map(|value| parse_timestamp(value, false))
+    {
+        Some(Some(from)) => from,
+        Some(None) => {
+            return Ok(BackendResponse::error(
+                "Invalid `from` timestamp.".into(),
+                StatusCode::BAD_REQUEST,
+            ))
+        }
+        None => to - chrono::Duration::days(period.days() * (DEFAULT_STATS_PERIODS - 1)),
+    };
+
+    if from > to {
+        return Ok(BackendResponse::error(
+            "`from` must be before `to`.".into(),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+    if (to - from).num_days() / period.days() >= MAX_STATS_PERIODS {
+        return Ok(BackendResponse::error(
+            format!(
+                "Error: The timeline can have at most {} periods.",
+                MAX_STATS_PERIODS
+            ),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let stats = state.db.get_stats(&period, from, to).await?;
+
+    Ok(BackendResponse::ok(
+        "Successfully fetched the stats.".into(),
+        stats,
+    ))
+}
+
+/// Maximum number of unranked candidates fetched from the database when looking for duplicates of a paper
+const MAX_SIMILAR_CANDIDATES: i64 = 200;
+/// Maximum number of ranked candidates returned when looking for duplicates of a paper
+const SIMILAR_CANDIDATES_LIMIT: usize = 20;
+
+/// Fetches the papers that may be duplicates of a paper, ranked by a score. Unlike [`similar`], this also matches course codes typed differently, similar course names, neighbouring years, and papers with the same or similar contents. Each candidate includes the reasons it matched.
+///
+/// # Request Query Parameters
+/// * `id`: The id of the paper. (required)
+pub async fn similar_candidates(
+    State(state): HandlerState,
+    Query(params): Query<HashMap<String, String>>,
+) -> HandlerReturn<Vec<SimilarCandidate>> {
+    let Some(Ok(id)) = params.get("id").map(|id| id.parse::<i32>()) else {
+        return Ok(BackendResponse::error(
+            "Invalid or missing paper id.".into(),
+            StatusCode::BAD_REQUEST,
+        ));
+    };
+
+    let candidates = state
+        .db
+        .get_similar_candidates(id, MAX_SIMILAR_CANDIDATES)
+        .await?;
+
+    let candidates = duplicates::rank_candidates(candidates, SIMILAR_CANDIDATES_LIMIT)
+        .into_iter()
+        .map(|candidate| {
+            Ok(SimilarCandidate {
+                paper: candidate.paper.with_url(&state.env_vars)?,
+                ..candidate
+            })
+        })
+        .collect::<Result<Vec<SimilarCandidate>>>()?;
+
+    Ok(BackendResponse::ok(
+        format!("Found {} candidate duplicates.", candidates.len()),
+        candidates,
+    ))
+}
+
+/// Starts a scan of all the approved and library papers for duplicates. The scan runs in the background and its results are fetched using [`get_duplicates`].
+pub async fn start_duplicate_scan(
+    Extension(auth): Extension<Auth>,
+    State(state): HandlerState,
+) -> HandlerReturn<i32> {
+    if let Some(scan_id) = state.db.insert_duplicate_scan(&auth.username).await? {
+        state.duplicate_scan_notify.notify_one();
+
+        Ok(BackendResponse::accepted(
+            "Duplicate scan started.".into(),
+            scan_id,
+        ))
+    } else {
+        Ok(BackendResponse::error(
+            "Error: A duplicate scan is already running.".into(),
+            StatusCode::CONFLICT,
+        ))
+    }
+}
+
+#[derive(Serialize)]
+/// A cluster of likely duplicates found by a duplicate scan
+pub struct DuplicateClusterRes {
+    /// The id of the paper suggested to be kept
+    pub keeper_id: i32,
+    /// Why the papers were grouped together
+    pub reasons: Vec<String>,
+    /// The papers in the cluster that have not been deleted since the scan
+    pub papers: Vec<AdminDashboardQP>,
+}
+
+#[derive(Serialize)]
+/// The response format for the duplicates endpoint
+pub struct DuplicatesRes {
+    pub scan: DuplicateScan,
+    /// The clusters found by the scan. Clusters with fewer than two papers left are skipped.
+    pub clusters: Vec<DuplicateClusterRes>,
+}
+
+/// Fetches the latest scan for duplicates (see [`start_duplicate_scan`]) along with the clusters of likely duplicates it found.
+pub async fn get_duplicates(State(state): HandlerState) -> HandlerReturn<DuplicatesRes> {
+    let Some((scan, clusters)) = state.db.get_latest_duplicate_scan().await? else {
+        return Ok(BackendResponse::error(
+            "Error: No duplicate scan has been run yet.".into(),
+            StatusCode::NOT_FOUND,
+        ));
+    };
+
+    let ids = clusters
+        .iter()
+        .flat_map(|cluster| cluster.paper_ids.iter().copied())
+        .collect::<Vec<i32>>();
+    let mut papers = state
+        .db
+        .get_papers_by_ids(&ids)
+        .await?
+        .into_iter()
+        .map(|paper| Ok((paper.qp.id, paper.with_url(&state.env_vars)?)))
+        .collect::<Result<HashMap<i32, AdminDashboardQP>>>()?;
+
+    let clusters = clusters
+        .into_iter()
+        .map(|cluster| DuplicateClusterRes {
+            keeper_id: cluster.keeper_id,
+            reasons: cluster.reasons,
+            papers: cluster
+                .paper_ids
+                .iter()
+                .filter_map(|id| papers.remove(id))
+                .collect(),
+        })
+        .filter(|cluster| cluster.papers.len() >= 2)
+        .collect::<Vec<DuplicateClusterRes>>();
+
+    Ok(BackendResponse::ok(
+        format!(
+            "Duplicate scan is {}. Found {} clusters.",
+            scan.status,
+            clusters.len()
+        ),
+        DuplicatesRes { scan, clusters },
+    ))
 }
 
 /// Fetches all question papers that match one or more properties specified. `course_name` is compulsory.
@@ -587,3 +2876,248 @@ pub async fn similar(
         papers,
     ))
 }
+
+/// Gets all the departments along with the number of published papers and registered courses of each department, ordered by name.
+pub async fn get_departments(State(state): HandlerState) -> HandlerReturn<Vec<Department>> {
+    let departments = state.db.get_departments().await?;
+
+    Ok(BackendResponse::ok(
+        format!("Successfully fetched {} departments.", departments.len()),
+        departments,
+    ))
+}
+
+/// Adds a department, or updates it if the department code exists. The code and prefixes are normalized (see [`DepartmentDetails::normalize`]).
+///
+/// Request format - [`DepartmentDetails`]
+pub async fn upsert_department(
+    State(state): HandlerState,
+    Json(body): Json<DepartmentDetails>,
+) -> HandlerReturn<DepartmentDetails> {
+    let department = match body.normalize() {
+        Ok(department) => department,
+        Err(e) => {
+            return Ok(BackendResponse::error(
+                format!("Error: {}", e),
+                StatusCode::BAD_REQUEST,
+            ))
+        }
+    };
+
+    state.db.upsert_department(&department).await?;
+
+    Ok(BackendResponse::ok(
+        "Successfully saved the department.".into(),
+        department,
+    ))
+}
+
+#[derive(Deserialize)]
+/// The request format for the delete department endpoint
+pub struct DeleteDepartmentReq {
+    code: String,
+}
+
+/// Removes a department. Papers and courses of the department are not changed, their department becomes unknown (unless another department matches them).
+///
+/// Request format - [`DeleteDepartmentReq`]
+pub async fn delete_department(
+    State(state): HandlerState,
+    Json(body): Json<DeleteDepartmentReq>,
+) -> HandlerReturn<()> {
+    if state
+        .db
+        .delete_department(&departments::normalize_department_code(&body.code))
+        .await?
+    {
+        Ok(BackendResponse::ok(
+            "Successfully deleted the department.".into(),
+            (),
+        ))
+    } else {
+        Ok(BackendResponse::error(
+            "Error: Department not found.".into(),
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(filename: &str, files: &[&str]) -> FileDetails {
+        FileDetails {
+            course_code: "CS10001".into(),
+            course_name: "Programming and Data Structures".into(),
+            year: 2024,
+            exam: "midsem".into(),
+            semester: "autumn".into(),
+            filename: filename.into(),
+            note: "".into(),
+            files: files.iter().map(|&file| file.into()).collect(),
+            attachment_of: None,
+            attachment_kind: None,
+        }
+    }
+
+    fn file(filename: &str) -> UploadedFile {
+        (
+            Some(filename.into()),
+            HeaderMap::new(),
+            Bytes::from(filename.to_owned()),
+        )
+    }
+
+    /// Returns the filename and message of each error, sorted by filename
+    fn error_messages(errors: Vec<UploadStatus>) -> Vec<(String, String)> {
+        let mut errors = errors
+            .into_iter()
+            .map(|status| {
+                assert_eq!(status.status, "error");
+                (status.filename, status.message)
+            })
+            .collect::<Vec<_>>();
+        errors.sort();
+        errors
+    }
+
+    #[test]
+    fn files_are_matched_by_filename_regardless_of_order() {
+        let (uploads, errors) = match_files(
+            vec![file("b.pdf"), file("a.pdf")],
+            vec![details("a.pdf", &[]), details("b.pdf", &[])],
+        );
+
+        assert!(errors.is_empty());
+        let matched = uploads
+            .iter()
+            .map(|(files, details)| (details.filename.as_str(), files[0].1.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            matched,
+            vec![
+                ("a.pdf", Bytes::from("a.pdf")),
+                ("b.pdf", Bytes::from("b.pdf"))
+            ]
+        );
+    }
+
+    #[test]
+    fn multi_file_papers_keep_the_part_order() {
+        let (uploads, errors) = match_files(
+            vec![file("page2.pdf"), file("page1.pdf")],
+            vec![details("paper.pdf", &["page1.pdf", "page2.pdf"])],
+        );
+
+        assert!(errors.is_empty());
+        assert_eq!(uploads.len(), 1);
+        let parts = uploads[0]
+            .0
+            .iter()
+            .map(|(_, data)| data.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parts,
+            vec![Bytes::from("page1.pdf"), Bytes::from("page2.pdf")]
+        );
+    }
+
+    #[test]
+    fn missing_and_extra_files_are_reported() {
+        let (uploads, errors) = match_files(
+            vec![file("a.pdf"), file("extra.pdf")],
+            vec![details("a.pdf", &[]), details("missing.pdf", &[])],
+        );
+
+        assert_eq!(uploads.len(), 1);
+        assert_eq!(
+            error_messages(errors),
+            vec![
+                (
+                    "extra.pdf".into(),
+                    "Error: No file details were found for this file.".into()
+                ),
+                (
+                    "missing.pdf".into(),
+                    "Error: No file was uploaded for these file details.".into()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_parts_reject_the_whole_paper() {
+        let (uploads, errors) = match_files(
+            vec![file("page1.pdf")],
+            vec![details("paper.pdf", &["page1.pdf", "page2.pdf"])],
+        );
+
+        assert!(uploads.is_empty());
+        assert_eq!(
+            error_messages(errors),
+            vec![(
+                "paper.pdf".into(),
+                "Error: No file was uploaded for the part `page2.pdf`.".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn duplicate_filenames_are_not_matched() {
+        let (uploads, errors) = match_files(
+            vec![file("a.pdf"), file("a.pdf"), file("b.pdf")],
+            vec![details("a.pdf", &[]), details("b.pdf", &[])],
+        );
+
+        assert_eq!(uploads.len(), 1);
+        assert_eq!(uploads[0].1.filename, "b.pdf");
+        assert_eq!(
+            error_messages(errors),
+            vec![
+                (
+                    "a.pdf".into(),
+                    "Error: Multiple files were uploaded with this filename.".into()
+                );
+                2
+            ]
+        );
+    }
+
+    #[test]
+    fn files_referenced_by_multiple_details_are_not_matched() {
+        let (uploads, errors) = match_files(
+            vec![file("a.pdf")],
+            vec![details("a.pdf", &[]), details("paper.pdf", &["a.pdf"])],
+        );
+
+        assert!(uploads.is_empty());
+        assert_eq!(
+            error_messages(errors),
+            vec![
+                (
+                    "a.pdf".into(),
+                    "Error: Multiple file details entries were found for the same file.".into()
+                ),
+                (
+                    "paper.pdf".into(),
+                    "Error: Multiple file details entries were found for the same file.".into()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn files_without_a_filename_are_reported() {
+        let (uploads, errors) = match_files(vec![(None, HeaderMap::new(), Bytes::new())], vec![]);
+
+        assert!(uploads.is_empty());
+        assert_eq!(
+            error_messages(errors),
+            vec![(
+                "".into(),
+                "Error: A file was uploaded without a filename.".into()
+            )]
+        );
+    }
+}
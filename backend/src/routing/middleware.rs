@@ -4,14 +4,17 @@ use axum::{
     extract::{Request, State},
     middleware::Next,
     response::{IntoResponse, Response},
+    Extension,
 };
 use http::{HeaderMap, StatusCode};
 
-use crate::auth;
+use crate::auth::{self, Auth, Role};
 
 use super::{AppError, BackendResponse, HandlerState};
 
 /// Verifies the JWT and authenticates a user. If the JWT is invalid, the user is sent an unauthorized status code. If the JWT is valid, the authentication is added to the state.
+///
+/// The role in the JWT may be outdated (e.g. the admin was demoted after logging in), so the authenticated user's role is always taken from the database.
 pub async fn verify_jwt_middleware(
     State(state): HandlerState,
     headers: HeaderMap,
@@ -22,7 +25,16 @@ pub async fn verify_jwt_middleware(
         if let Some(jwt) = auth_header.to_str()?.strip_prefix("Bearer ") {
             let auth = auth::verify_token(jwt.to_owned(), &state.env_vars).await;
 
-            if let Ok(auth) = auth {
+            if let Ok(mut auth) = auth {
+                let Some(role) = state.db.get_admin_role(&auth.username).await? else {
+                    return Ok(BackendResponse::<()>::error(
+                        "Authorization token invalid.".into(),
+                        StatusCode::UNAUTHORIZED,
+                    )
+                    .into_response());
+                };
+                auth.role = role;
+
                 // If auth is fine, add it to the request extensions
                 request.extensions_mut().insert(auth);
                 Ok(next.run(request).await)
@@ -48,3 +60,24 @@ pub async fn verify_jwt_middleware(
         .into_response())
     }
 }
+
+/// Checks whether the authenticated user has at least the given role (the middleware's state). Must be used after [`verify_jwt_middleware`], which sets the user's current role from the database. If the user's role is lower, the user is sent a forbidden status code.
+pub async fn require_role_middleware(
+    State(role): State<Role>,
+    Extension(auth): Extension<Auth>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if auth.role >= role {
+        next.run(request).await
+    } else {
+        BackendResponse::<()>::error(
+            format!(
+                "This action requires the `{}` role or higher.",
+                String::from(&role)
+            ),
+            StatusCode::FORBIDDEN,
+        )
+        .into_response()
+    }
+}
@@ -2,6 +2,8 @@
 
 use std::sync::Arc;
 
+use tokio::sync::Notify;
+
 use axum::{
     extract::{DefaultBodyLimit, Json, State},
     http::StatusCode,
@@ -15,16 +17,20 @@ use tower_http::{
 };
 
 use crate::{
+    auth::Role,
     db::{self, Database},
     env::EnvVars,
+    scanner::{self, Scanner},
 };
 
 mod handlers;
 mod middleware;
+mod workers;
 
-pub use handlers::{EditReq, FileDetails};
+pub use handlers::{EditReq, FileDetails, PaperListReq, UploadJob, UploadStatus};
+pub use workers::StagedFile;
 
-/// Returns the Axum router for IQPS
+/// Returns the Axum router for IQPS. Also spawns the background [`workers`].
 pub fn get_router(env_vars: EnvVars, db: Database) -> axum::Router {
     let cors_origins = env_vars
         .cors_allowed_origins
@@ -37,17 +43,98 @@ pub fn get_router(env_vars: EnvVars, db: Database) -> axum::Router {
         })
         .collect::<Vec<HeaderValue>>();
 
-    let state = Arc::new(RouterState { db, env_vars });
+    let scanner = scanner::get_scanner(&env_vars);
+    let state = Arc::new(RouterState {
+        db,
+        env_vars,
+        scanner,
+        upload_notify: Notify::new(),
+        duplicate_scan_notify: Notify::new(),
+    });
+
+    tokio::spawn(workers::upload_worker(state.clone()));
+    tokio::spawn(workers::trash_purge_worker(state.clone()));
+    tokio::spawn(workers::duplicate_scan_worker(state.clone()));
+
+    // Admin routes are grouped by the minimum role required to use them
+    let reviewer_routes = axum::Router::new()
+        .route("/edit", axum::routing::post(handlers::edit))
+        .route("/claim", axum::routing::post(handlers::claim))
+        .route("/unclaim", axum::routing::post(handlers::unclaim))
+        .route("/bulk-edit", axum::routing::post(handlers::bulk_edit))
+        .route("/merge", axum::routing::post(handlers::merge))
+        .route("/pages", axum::routing::post(handlers::edit_pages))
+        .route(
+            "/pages/extract",
+            axum::routing::post(handlers::extract_pages),
+        )
+        .route("/revert", axum::routing::post(handlers::revert))
+        .route("/delete", axum::routing::post(handlers::delete))
+        .route("/reject", axum::routing::post(handlers::reject))
+        .route("/restore", axum::routing::post(handlers::restore))
+        .route("/tags", axum::routing::post(handlers::create_tag))
+        .route("/tags/edit", axum::routing::post(handlers::update_tag))
+        .route_layer(axum::middleware::from_fn_with_state(
+            Role::Reviewer,
+            middleware::require_role_middleware,
+        ));
+
+    let maintainer_routes = axum::Router::new()
+        .route("/harddelete", axum::routing::post(handlers::hard_delete))
+        .route("/audit", axum::routing::get(handlers::get_audit_log))
+        .route(
+            "/duplicates/scan",
+            axum::routing::post(handlers::start_duplicate_scan),
+        )
+        .route("/tags/delete", axum::routing::post(handlers::delete_tag))
+        .route("/courses", axum::routing::post(handlers::upsert_course))
+        .route(
+            "/courses/delete",
+            axum::routing::post(handlers::delete_course),
+        )
+        .route(
+            "/courses/import",
+            axum::routing::post(handlers::import_courses),
+        )
+        .route(
+            "/departments",
+            axum::routing::post(handlers::upsert_department),
+        )
+        .route(
+            "/departments/delete",
+            axum::routing::post(handlers::delete_department),
+        )
+        .route_layer(axum::middleware::from_fn_with_state(
+            Role::Maintainer,
+            middleware::require_role_middleware,
+        ));
+
+    let owner_routes = axum::Router::new()
+        .route(
+            "/roles",
+            axum::routing::get(handlers::get_roles).post(handlers::set_role),
+        )
+        .route_layer(axum::middleware::from_fn_with_state(
+            Role::Owner,
+            middleware::require_role_middleware,
+        ));
 
     axum::Router::new()
         .route("/unapproved", axum::routing::get(handlers::get_unapproved))
         .route("/trash", axum::routing::get(handlers::get_trash))
         .route("/details", axum::routing::get(handlers::get_paper_details))
         .route("/profile", axum::routing::get(handlers::profile))
-        .route("/edit", axum::routing::post(handlers::edit))
-        .route("/delete", axum::routing::post(handlers::delete))
-        .route("/harddelete", axum::routing::post(handlers::hard_delete))
         .route("/similar", axum::routing::get(handlers::similar))
+        .route(
+            "/similar/candidates",
+            axum::routing::get(handlers::similar_candidates),
+        )
+        .route("/history", axum::routing::get(handlers::get_history))
+        .route("/stats", axum::routing::get(handlers::get_stats))
+        .route("/duplicates", axum::routing::get(handlers::get_duplicates))
+        .merge(reviewer_routes)
+        .merge(maintainer_routes)
+        .merge(owner_routes)
         .route_layer(axum::middleware::from_fn_with_state(
             state.clone(),
             middleware::verify_jwt_middleware,
@@ -55,7 +142,18 @@ pub fn get_router(env_vars: EnvVars, db: Database) -> axum::Router {
         .route("/oauth", axum::routing::post(handlers::oauth))
         .route("/healthcheck", axum::routing::get(handlers::healthcheck))
         .route("/search", axum::routing::get(handlers::search))
+        .route("/tags", axum::routing::get(handlers::get_tags))
+        .route("/courses", axum::routing::get(handlers::get_courses))
+        .route(
+            "/departments",
+            axum::routing::get(handlers::get_departments),
+        )
+        .route("/paper/{id}", axum::routing::get(handlers::paper))
         .layer(DefaultBodyLimit::max(2 << 20)) // Default limit of 2 MiB
+        .route(
+            "/upload/jobs/{token}",
+            axum::routing::get(handlers::get_upload_job),
+        )
         .route("/upload", axum::routing::post(handlers::upload))
         .layer(DefaultBodyLimit::max(50 << 20)) // 50 MiB limit for upload endpoint
         .with_state(state)
@@ -72,10 +170,16 @@ pub fn get_router(env_vars: EnvVars, db: Database) -> axum::Router {
         )
 }
 
-/// The state of the axum router, containing the environment variables and the database connection.
+/// The state of the axum router, containing the environment variables, the database connection, and the malware scanner.
 struct RouterState {
     pub db: db::Database,
     pub env_vars: EnvVars,
+    /// The malware scanner used for uploads
+    pub scanner: Box<dyn Scanner>,
+    /// Used to wake up the upload worker when a new upload job is created
+    pub upload_notify: Notify,
+    /// Used to wake up the duplicate scan worker when a new scan is started
+    pub duplicate_scan_notify: Notify,
 }
 type HandlerState = State<Arc<RouterState>>;
 
@@ -86,7 +190,7 @@ struct BackendResponse<T: Serialize> {
     pub status: &'static str,
     /// A message describing the state of the operation (success/failure message)
     pub message: String,
-    /// Any optional data sent (only sent if the operation was a success)
+    /// Any optional data sent (only sent if the operation was a success, or if the error can be recovered from using the data)
     pub data: Option<T>,
 }
 
@@ -103,6 +207,34 @@ impl<T: serde::Serialize> BackendResponse<T> {
         )
     }
 
+    /// Creates a new success backend response with the given message and data, for requests that are accepted but processed later
+    pub fn accepted(message: String, data: T) -> (StatusCode, Self) {
+        (
+            StatusCode::ACCEPTED,
+            Self {
+                status: "success",
+                message,
+                data: Some(data),
+            },
+        )
+    }
+
+    /// Creates a new error backend response with the given message, data, and an HTTP status code. Used when the client needs data to recover from the error.
+    pub fn error_with_data(
+        message: String,
+        data: T,
+        status_code: StatusCode,
+    ) -> (StatusCode, Self) {
+        (
+            status_code,
+            Self {
+                status: "error",
+                message,
+                data: Some(data),
+            },
+        )
+    }
+
     /// Creates a new error backend response with the given message, data, and an HTTP status code
     pub fn error(message: String, status_code: StatusCode) -> (StatusCode, Self) {
         (
@@ -0,0 +1,747 @@
+//! Background workers that run alongside the router and share its state.
+
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use color_eyre::eyre::Result;
+use lopdf::Document;
+use sha2::{Digest, Sha256};
+use sqlx::types::Json;
+use tokio::fs;
+
+use crate::{
+    duplicates::{self, DuplicateCluster},
+    pathutils::PaperCategory,
+    pdf,
+    scanner::{self, ScanPolicy},
+    slack::send_slack_message,
+};
+
+use super::{handlers, FileDetails, RouterState, UploadStatus};
+
+/// 10 MiB file size limit
+const FILE_SIZE_LIMIT: usize = 10 << 20;
+
+/// How often the upload worker checks for new jobs if it is not notified
+const UPLOAD_WORKER_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Maximum number of characters of a paper's text stored to find duplicates
+const MAX_CONTENT_TEXT_CHARS: usize = 5000;
+
+/// How often the trash purge worker checks for expired papers in the trash
+const TRASH_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The username recorded in the audit log for papers purged from the trash
+const TRASH_PURGE_USERNAME: &str = "trash-purge";
+
+/// How often the duplicate scan worker checks for new scans if it is not notified
+const DUPLICATE_SCAN_POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(sqlx::FromRow)]
+/// A file of an upload job, staged in the staging directory until it is processed
+pub struct StagedFile {
+    /// The id of the file in the upload job
+    pub id: i32,
+    pub filename: String,
+    pub file_details: Json<FileDetails>,
+    /// The `content-type` header sent with each part of the paper (empty if not sent)
+    pub content_types: Vec<String>,
+    /// The names of the files in the staging directory which make up the paper, in order
+    pub staged_files: Vec<String>,
+}
+
+/// Processes upload jobs in the background. Jobs are stored in the database, so a job interrupted by a restart is picked up again.
+///
+/// The worker is woken up by the upload handler using [`RouterState::upload_notify`] and also polls the database periodically.
+pub async fn upload_worker(state: Arc<RouterState>) {
+    if let Err(e) = state.db.reset_interrupted_upload_jobs().await {
+        tracing::error!("Error resetting interrupted upload jobs: {}", e);
+    }
+
+    loop {
+        match process_next_upload_job(&state).await {
+            // Check for more jobs right away
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => tracing::error!("Error processing upload job: {}", e),
+        }
+
+        tokio::select! {
+            _ = state.upload_notify.notified() => {}
+            _ = tokio::time::sleep(UPLOAD_WORKER_POLL_INTERVAL) => {}
+        }
+    }
+}
+
+/// Permanently deletes papers that have been in the trash for longer than `TRASH_RETENTION_DAYS`. Does nothing if the retention period is 0.
+pub async fn trash_purge_worker(state: Arc<RouterState>) {
+    if state.env_vars.trash_retention_days <= 0 {
+        return;
+    }
+
+    loop {
+        if let Err(e) = purge_expired_trash(&state).await {
+            tracing::error!("Error purging the trash: {}", e);
+        }
+
+        tokio::time::sleep(TRASH_PURGE_INTERVAL).await;
+    }
+}
+
+/// Permanently deletes the expired papers in the trash and sends a summary to Slack.
+async fn purge_expired_trash(state: &RouterState) -> Result<()> {
+    let retention_days = state.env_vars.trash_retention_days;
+    let expired_ids = state.db.get_expired_trash(retention_days).await?;
+    if expired_ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut purged_count = 0;
+    let mut failed_ids = Vec::<i32>::new();
+    for id in expired_ids {
+        let purged = match state.db.get_paper_by_id(id).await {
+            Ok(paper) => handlers::hard_delete_paper(state, &paper, TRASH_PURGE_USERNAME)
+                .await
+                .map(|status| status.is_ok())
+                .unwrap_or_else(|e| {
+                    tracing::error!("Error purging paper {}: {}", id, e);
+                    false
+                }),
+            Err(e) => {
+                tracing::error!("Error fetching paper {} to purge: {}", id, e);
+                false
+            }
+        };
+
+        if purged {
+            purged_count += 1;
+        } else {
+            failed_ids.push(id);
+        }
+    }
+
+    tracing::info!(
+        "Purged {} papers older than {} days from the trash. Failed to purge {} papers: {:?}",
+        purged_count,
+        retention_days,
+        failed_ids.len(),
+        failed_ids
+    );
+
+    let message = format!(
+        "🗑️ Permanently deleted *{}* papers that were in the trash for more than {} days.{}",
+        purged_count,
+        retention_days,
+        if failed_ids.is_empty() {
+            "".into()
+        } else {
+            format!(
+                "\n\nFailed to delete {} papers (ids: {}).",
+                failed_ids.len(),
+                failed_ids
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+        }
+    );
+
+    let _ = send_slack_message(&state.env_vars.slack_webhook_url, &message).await;
+
+    Ok(())
+}
+
+/// Runs scans of the archive for duplicates in the background. Like upload jobs, scans are stored in the database and a scan interrupted by a restart is run again.
+///
+/// The worker is woken up by the handler that starts a scan using [`RouterState::duplicate_scan_notify`] and also polls the database periodically.
+pub async fn duplicate_scan_worker(state: Arc<RouterState>) {
+    if let Err(e) = state.db.reset_interrupted_duplicate_scans().await {
+        tracing::error!("Error resetting interrupted duplicate scans: {}", e);
+    }
+
+    loop {
+        match process_next_duplicate_scan(&state).await {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => tracing::error!("Error processing duplicate scan: {}", e),
+        }
+
+        tokio::select! {
+            _ = state.duplicate_scan_notify.notified() => {}
+            _ = tokio::time::sleep(DUPLICATE_SCAN_POLL_INTERVAL) => {}
+        }
+    }
+}
+
+/// Runs the oldest pending duplicate scan and sends a summary to Slack. Returns `false` if there were no pending scans.
+async fn process_next_duplicate_scan(state: &RouterState) -> Result<bool> {
+    let Some(scan_id) = state.db.claim_next_duplicate_scan().await? else {
+        return Ok(false);
+    };
+
+    let (paper_count, clusters) = match scan_for_duplicates(state).await {
+        Ok(result) => result,
+        Err(e) => {
+            state.db.fail_duplicate_scan(scan_id).await?;
+
+            return Err(e);
+        }
+    };
+
+    state
+        .db
+        .finish_duplicate_scan(scan_id, paper_count as i32, &clusters)
+        .await?;
+
+    let duplicate_count = clusters
+        .iter()
+        .map(|cluster| cluster.paper_ids.len() - 1)
+        .sum::<usize>();
+    tracing::info!(
+        "Duplicate scan {} found {} clusters ({} duplicates) among {} papers.",
+        scan_id,
+        clusters.len(),
+        duplicate_count,
+        paper_count
+    );
+
+    let message = format!(
+        "🔍 Duplicate scan finished. Found *{}* clusters of likely duplicates ({} papers that can be removed) among {} papers.",
+        clusters.len(),
+        duplicate_count,
+        paper_count
+    );
+    let _ = send_slack_message(&state.env_vars.slack_webhook_url, &message).await;
+
+    Ok(true)
+}
+
+/// Groups all the approved and library papers into clusters of likely duplicates. Returns the number of papers scanned and the clusters.
+async fn scan_for_duplicates(state: &RouterState) -> Result<(usize, Vec<DuplicateCluster>)> {
+    let papers = state.db.get_duplicate_scan_papers().await?;
+    let near_identical_pairs = state
+        .db
+        .get_near_identical_text_pairs(duplicates::NEAR_IDENTICAL_TEXT_SIMILARITY)
+        .await?;
+
+    let paper_count = papers.len();
+    let clusters = tokio::task::spawn_blocking(move || {
+        duplicates::find_clusters(&papers, &near_identical_pairs)
+    })
+    .await?;
+
+    Ok((paper_count, clusters))
+}
+
+/// Processes the oldest pending upload job. Returns `false` if there were no pending jobs.
+async fn process_next_upload_job(state: &RouterState) -> Result<bool> {
+    let Some((job_id, atomic)) = state.db.claim_next_upload_job().await? else {
+        return Ok(false);
+    };
+
+    let files = state.db.get_pending_upload_job_files(job_id).await?;
+    let staged_paths = files
+        .iter()
+        .flat_map(|file| &file.staged_files)
+        .map(|staged_file| state.env_vars.paths.get_staging_path(staged_file))
+        .collect::<Vec<PathBuf>>();
+
+    let uploaded_count = if atomic {
+        process_atomic(state, files).await?
+    } else {
+        process_each(state, files).await?
+    };
+
+    for staged_path in staged_paths {
+        if let Err(e) = fs::remove_file(&staged_path).await {
+            tracing::error!(
+                "Error removing staged file {}: {}",
+                staged_path.to_string_lossy(),
+                e
+            );
+        }
+    }
+
+    state.db.finish_upload_job(job_id).await?;
+
+    if uploaded_count > 0 {
+        let total_count = state.db.get_unapproved_papers_count().await?;
+        let message = format!(
+            "🔔 {} uploaded to IQPS!\n\n<https://qp.metakgp.org/admin|Review> | Total Unapproved papers: *{}*",
+            if uploaded_count == 1 {
+                "A new paper was".into()
+            } else {
+                format!("{} new papers were", uploaded_count)
+            },
+            total_count
+        );
+
+        let _ = send_slack_message(&state.env_vars.slack_webhook_url, &message).await;
+    }
+
+    Ok(true)
+}
+
+/// Validates an uploaded file and scans it for malware.
+///
+/// Returns an error message if the file can not be accepted, `Some(signature)` if the file is infected and should be quarantined, or `None` if the file is fine.
+async fn check_file(
+    state: &RouterState,
+    content_type: &str,
+    file_data: &[u8],
+) -> Result<Option<String>, String> {
+    if file_data.len() > FILE_SIZE_LIMIT {
+        return Err(format!(
+            "File size too big. Only files upto {} MiB are allowed.",
+            FILE_SIZE_LIMIT >> 20
+        ));
+    }
+
+    check_content_type(content_type)?;
+
+    match scanner::scan_file(
+        state.scanner.as_ref(),
+        file_data,
+        state.env_vars.scanner_fail_open,
+    )
+    .await
+    {
+        ScanPolicy::Accept => Ok(None),
+        ScanPolicy::Quarantine(signature) => Ok(Some(signature)),
+        ScanPolicy::Reject => {
+            Err("Error: The file could not be scanned for malware. Please try again later.".into())
+        }
+    }
+}
+
+/// Checks whether the `content-type` of an uploaded file is supported. Returns an error message if not.
+fn check_content_type(content_type: &str) -> Result<(), String> {
+    if content_type.is_empty() {
+        Err("`content-type` header not found. File type could not be determined.".into())
+    } else if content_type != "application/pdf" {
+        Err("Only PDFs are supported.".into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Reads the staged files of a paper and merges them into a single PDF if there is more than one.
+///
+/// Returns the content type and the data of the paper, or an error message if the files could not be read or merged.
+async fn load_paper(state: &RouterState, file: &StagedFile) -> Result<(String, Vec<u8>), String> {
+    let mut parts = Vec::<Vec<u8>>::new();
+    for staged_file in &file.staged_files {
+        let staged_path = state.env_vars.paths.get_staging_path(staged_file);
+
+        match fs::read(&staged_path).await {
+            Ok(data) => parts.push(data),
+            Err(e) => {
+                tracing::error!(
+                    "Error reading staged file {}: {}",
+                    staged_path.to_string_lossy(),
+                    e
+                );
+
+                return Err("Error reading the uploaded file.".into());
+            }
+        }
+    }
+
+    if parts.len() == 1 {
+        let content_type = file.content_types.first().cloned().unwrap_or_default();
+        return Ok((content_type, parts.remove(0)));
+    }
+
+    // Only PDFs can be merged
+    for content_type in &file.content_types {
+        check_content_type(content_type)?;
+    }
+
+    let merged = tokio::task::spawn_blocking(move || {
+        let documents = parts
+            .iter()
+            .map(|part| pdf::load(part))
+            .collect::<Result<Vec<Document>>>()?;
+
+        pdf::merge(documents)
+    })
+    .await;
+
+    match merged {
+        Ok(Ok(data)) => Ok(("application/pdf".into(), data)),
+        Ok(Err(e)) => {
+            tracing::error!("Error merging the files of {}: {}", file.filename, e);
+
+            Err("Error: The files could not be merged into a single PDF.".into())
+        }
+        Err(e) => {
+            tracing::error!("Error merging the files of {}: {}", file.filename, e);
+
+            Err("Error: The files could not be merged into a single PDF.".into())
+        }
+    }
+}
+
+/// Returns the hash (hex encoded SHA-256) and the extracted text of a paper's file, used to find duplicates. The text is empty if it can not be extracted.
+pub(super) async fn get_paper_content(file_data: &[u8]) -> (String, String) {
+    let content_hash = Sha256::digest(file_data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+
+    let file_data = file_data.to_vec();
+    let content_text = tokio::task::spawn_blocking(move || {
+        pdf::load(&file_data)
+            .map(|document| pdf::extract_text(&document, MAX_CONTENT_TEXT_CHARS))
+            .unwrap_or_default()
+    })
+    .await
+    .unwrap_or_default();
+
+    (content_hash, content_text)
+}
+
+/// Uploads each paper independently, each in its own transaction. A failure only affects that paper.
+///
+/// The status of each file is saved as soon as it is processed. If a paper is inserted, its file's status is saved in the same transaction (see [`upload_file`]), so an interrupted job never inserts a paper twice. Returns the number of papers uploaded.
+async fn process_each(state: &RouterState, files: Vec<StagedFile>) -> Result<usize> {
+    let mut uploaded_count = 0;
+
+    for file in files {
+        let file_id = file.id;
+        state
+            .db
+            .set_upload_job_file_status(file_id, &UploadStatus::processing(file.filename.clone()))
+            .await?;
+
+        let status = match load_paper(state, &file).await {
+            Ok((content_type, file_data)) => {
+                let filename = file.filename.clone();
+
+                upload_file(state, file, &content_type, file_data)
+                    .await
+                    .unwrap_or_else(|e| {
+                        tracing::error!("Error uploading file {}: {}", filename, e);
+
+                        UploadStatus::error(
+                            filename,
+                            "An internal server error occured. Please try again later.".into(),
+                        )
+                    })
+            }
+            Err(message) => UploadStatus::error(file.filename, message),
+        };
+
+        if status.status == "success" {
+            uploaded_count += 1;
+        }
+        state
+            .db
+            .set_upload_job_file_status(file_id, &status)
+            .await?;
+    }
+
+    Ok(uploaded_count)
+}
+
+/// Uploads a single paper in its own transaction. The status of the file is saved in the transaction if the paper is inserted.
+async fn upload_file(
+    state: &RouterState,
+    file: StagedFile,
+    content_type: &str,
+    file_data: Vec<u8>,
+) -> Result<UploadStatus> {
+    let StagedFile {
+        id: file_id,
+        filename,
+        file_details: Json(details),
+        ..
+    } = file;
+
+    let quarantine_signature = match check_file(state, content_type, &file_data).await {
+        Ok(signature) => signature,
+        Err(message) => return Ok(UploadStatus::error(filename, message)),
+    };
+
+    // Infected files are stored in the quarantine directory (not served) for the reviewers to see
+    if let Some(signature) = quarantine_signature {
+        return quarantine_upload(state, file_id, filename, details, file_data, &signature).await;
+    }
+
+    // Insert the db entry
+    let (mut tx, id) = state.db.insert_new_uploaded_qp(details).await?;
+
+    // Create the new filelink (slug)
+    let filelink_slug = state
+        .env_vars
+        .paths
+        .get_slug(&format!("{}.pdf", id), PaperCategory::Unapproved);
+
+    let (content_hash, content_text) = get_paper_content(&file_data).await;
+    let status = UploadStatus::ok(filename.clone(), id);
+
+    // Update the filelink, the contents, and the status of the file in the db
+    if state
+        .db
+        .update_filelink(&mut tx, id, &filelink_slug)
+        .await
+        .is_ok()
+        && state
+            .db
+            .set_paper_content(&mut tx, id, &content_hash, &content_text)
+            .await
+            .is_ok()
+        && state
+            .db
+            .set_upload_job_file_status_in_tx(&mut tx, file_id, &status)
+            .await
+            .is_ok()
+    {
+        let filepath = state.env_vars.paths.get_path_from_slug(&filelink_slug);
+
+        // Write the file data. If the write fails, rollback the transaction, else commit it.
+        if fs::write(&filepath, file_data).await.is_ok() {
+            if tx.commit().await.is_ok() {
+                Ok(status)
+            } else {
+                // Transaction commit failed, delete the file
+                fs::remove_file(filepath).await?;
+                Ok(UploadStatus::error(
+                    filename,
+                    "Error: Database transaction failed.".into(),
+                ))
+            }
+        } else {
+            tx.rollback().await?;
+            Ok(UploadStatus::error(
+                filename,
+                "Error writing the file.".into(),
+            ))
+        }
+    } else {
+        tx.rollback().await?;
+        Ok(UploadStatus::error(
+            filename,
+            "Error updating the filelink".into(),
+        ))
+    }
+}
+
+/// Inserts an infected uploaded paper in its own transaction as a quarantined paper, and stores its file in the quarantine directory (not served) for the reviewers to see. The status of the file is saved in the transaction.
+async fn quarantine_upload(
+    state: &RouterState,
+    file_id: i32,
+    filename: String,
+    details: FileDetails,
+    file_data: Vec<u8>,
+    signature: &str,
+) -> Result<UploadStatus> {
+    let (mut tx, id) = state.db.insert_new_uploaded_qp(details).await?;
+
+    tracing::warn!(
+        "Quarantining uploaded paper {} ({}): {}",
+        id,
+        filename,
+        signature
+    );
+
+    let quarantine_filename = format!("{}.pdf", id);
+    let filepath = state
+        .env_vars
+        .paths
+        .get_quarantine_path(&quarantine_filename);
+    let status = UploadStatus::error(
+        filename,
+        "The file was flagged by the malware scanner and will not be published.".into(),
+    );
+
+    if state
+        .db
+        .quarantine_paper(&mut tx, id, &quarantine_filename)
+        .await
+        .is_ok()
+        && state
+            .db
+            .set_upload_job_file_status_in_tx(&mut tx, file_id, &status)
+            .await
+            .is_ok()
+        && fs::write(&filepath, file_data).await.is_ok()
+    {
+        if tx.commit().await.is_err() {
+            fs::remove_file(filepath).await?;
+        }
+    } else {
+        tx.rollback().await?;
+    }
+
+    Ok(status)
+}
+
+/// Uploads all the papers or none of them.
+///
+/// Every file is validated (and scanned) first. An infected file fails the whole batch, it is quarantined (like in a non-atomic upload) and the other files are not uploaded. All the papers are then inserted in a single transaction and the files are written. If anything fails, the transaction is rolled back and the files already written are removed.
+///
+/// The statuses of all the files are saved at the end. If the papers are inserted, the statuses are also saved in the same transaction, so an interrupted job never inserts the papers twice. Returns the number of papers uploaded.
+async fn process_atomic(state: &RouterState, files: Vec<StagedFile>) -> Result<usize> {
+    for file in &files {
+        state
+            .db
+            .set_upload_job_file_status(file.id, &UploadStatus::processing(file.filename.clone()))
+            .await?;
+    }
+
+    let file_ids = files
+        .iter()
+        .map(|file| (file.id, file.filename.clone()))
+        .collect::<Vec<(i32, String)>>();
+    let statuses = upload_atomic(state, files).await.unwrap_or_else(|e| {
+        tracing::error!("Error processing atomic upload: {}", e);
+
+        Vec::new()
+    });
+
+    // An empty list of statuses means the whole job failed unexpectedly
+    let uploaded_count = statuses
+        .iter()
+        .filter(|status| status.status == "success")
+        .count();
+    let mut statuses = statuses.into_iter();
+    for (file_id, filename) in file_ids {
+        let status = statuses.next().unwrap_or_else(|| {
+            UploadStatus::error(
+                filename,
+                "An internal server error occured. Please try again later.".into(),
+            )
+        });
+        state
+            .db
+            .set_upload_job_file_status(file_id, &status)
+            .await?;
+    }
+
+    Ok(uploaded_count)
+}
+
+/// See [`process_atomic`]. Returns the status of each file in order.
+async fn upload_atomic(state: &RouterState, files: Vec<StagedFile>) -> Result<Vec<UploadStatus>> {
+    let mut uploads = Vec::<(i32, FileDetails, Vec<u8>)>::new();
+    let mut check_errors = Vec::<Option<String>>::new();
+    let mut quarantine_signatures = Vec::<Option<String>>::new();
+    let mut filenames = Vec::<String>::new();
+
+    // Validate every file before touching the database
+    for file in files {
+        let (check_error, quarantine_signature, file_data) = match load_paper(state, &file).await {
+            Ok((content_type, file_data)) => {
+                match check_file(state, &content_type, &file_data).await {
+                    Ok(signature) => (None, signature, file_data),
+                    Err(message) => (Some(message), None, file_data),
+                }
+            }
+            Err(message) => (Some(message), None, Vec::new()),
+        };
+
+        check_errors.push(check_error);
+        quarantine_signatures.push(quarantine_signature);
+        uploads.push((file.id, file.file_details.0, file_data));
+        filenames.push(file.filename);
+    }
+
+    if check_errors.iter().any(Option::is_some) || quarantine_signatures.iter().any(Option::is_some)
+    {
+        let mut statuses = Vec::<UploadStatus>::new();
+        for (((file_id, details, file_data), filename), (error, signature)) in uploads
+            .into_iter()
+            .zip(filenames)
+            .zip(check_errors.into_iter().zip(quarantine_signatures))
+        {
+            let status = match (error, signature) {
+                (_, Some(signature)) => {
+                    quarantine_upload(state, file_id, filename, details, file_data, &signature)
+                        .await?
+                }
+                (Some(error), None) => UploadStatus::error(filename, error),
+                (None, None) => UploadStatus::error(
+                    filename,
+                    "Not uploaded: Other files in the batch failed validation.".into(),
+                ),
+            };
+            statuses.push(status);
+        }
+
+        return Ok(statuses);
+    }
+
+    let mut tx = state.db.begin_transaction().await?;
+    let mut written_files = Vec::<PathBuf>::new();
+    let mut statuses = Vec::<UploadStatus>::new();
+
+    let result: Result<()> = async {
+        for ((file_id, details, file_data), filename) in uploads.into_iter().zip(&filenames) {
+            let id = state.db.insert_uploaded_qp(&mut tx, details).await?;
+
+            let filelink_slug = state
+                .env_vars
+                .paths
+                .get_slug(&format!("{}.pdf", id), PaperCategory::Unapproved);
+            state
+                .db
+                .update_filelink(&mut tx, id, &filelink_slug)
+                .await?;
+
+            let (content_hash, content_text) = get_paper_content(&file_data).await;
+            state
+                .db
+                .set_paper_content(&mut tx, id, &content_hash, &content_text)
+                .await?;
+
+            let filepath = state.env_vars.paths.get_path_from_slug(&filelink_slug);
+            fs::write(&filepath, file_data).await?;
+            written_files.push(filepath);
+
+            let status = UploadStatus::ok(filename.clone(), id);
+            state
+                .db
+                .set_upload_job_file_status_in_tx(&mut tx, file_id, &status)
+                .await?;
+            statuses.push(status);
+        }
+
+        Ok(())
+    }
+    .await;
+
+    let result = match result {
+        Ok(()) => tx.commit().await.map_err(color_eyre::eyre::Error::from),
+        Err(e) => {
+            tx.rollback().await?;
+            Err(e)
+        }
+    };
+
+    if let Err(e) = result {
+        tracing::error!("Atomic upload failed, rolling back: {}", e);
+
+        for filepath in written_files {
+            if let Err(e) = fs::remove_file(&filepath).await {
+                tracing::error!(
+                    "Error removing file {} after rollback: {}",
+                    filepath.to_string_lossy(),
+                    e
+                );
+            }
+        }
+
+        return Ok(filenames
+            .into_iter()
+            .map(|filename| {
+                UploadStatus::error(
+                    filename,
+                    "Error: The batch upload failed. No papers were uploaded.".into(),
+                )
+            })
+            .collect());
+    }
+
+    Ok(statuses)
+}
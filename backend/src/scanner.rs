@@ -0,0 +1,260 @@
+//! Malware scanning for uploaded files.
+//!
+//! Every uploaded file is passed through a [`Scanner`] before it is written to a publicly served directory. The reference implementation [`ClamdScanner`] talks to a local `clamd` daemon over its unix socket.
+
+use std::{future::Future, path::PathBuf, pin::Pin};
+
+use color_eyre::eyre::eyre;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::UnixStream,
+};
+
+use crate::env::EnvVars;
+
+/// A boxed future returned by [`Scanner::scan`]. Needed to keep the trait object safe.
+pub type ScanFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<ScanVerdict, color_eyre::eyre::Error>> + Send + 'a>>;
+
+/// The result of scanning a file
+pub enum ScanVerdict {
+    /// No threats were found
+    Clean,
+    /// The file is infected, contains the name of the signature that matched
+    Infected(String),
+}
+
+/// An interface to a malware scanner.
+///
+/// An `Err` returned from [`Scanner::scan`] means the scanner itself is unavailable (not that the file is infected). Whether the upload then goes through is decided by [`ScanPolicy`].
+pub trait Scanner: Send + Sync {
+    /// Scans the given file contents
+    fn scan<'a>(&'a self, data: &'a [u8]) -> ScanFuture<'a>;
+}
+
+/// A scanner that considers every file clean. Used when no scanner is configured.
+pub struct NoopScanner;
+
+impl Scanner for NoopScanner {
+    fn scan<'a>(&'a self, _data: &'a [u8]) -> ScanFuture<'a> {
+        Box::pin(async { Ok(ScanVerdict::Clean) })
+    }
+}
+
+/// Size of each chunk sent to clamd in the `INSTREAM` command
+const CLAMD_CHUNK_SIZE: usize = 64 << 10;
+
+/// A scanner that streams the file to a local clamd daemon using the `INSTREAM` command.
+///
+/// See `man clamd` for the protocol.
+pub struct ClamdScanner {
+    /// Path to the clamd unix socket
+    socket_path: PathBuf,
+}
+
+impl ClamdScanner {
+    pub fn new(socket_path: PathBuf) -> Self {
+        Self { socket_path }
+    }
+
+    async fn scan_stream(&self, data: &[u8]) -> Result<ScanVerdict, color_eyre::eyre::Error> {
+        let mut stream = UnixStream::connect(&self.socket_path).await?;
+
+        // `z` prefix means the command and the reply are null-terminated
+        stream.write_all(b"zINSTREAM\0").await?;
+        for chunk in data.chunks(CLAMD_CHUNK_SIZE) {
+            stream
+                .write_all(&(chunk.len() as u32).to_be_bytes())
+                .await?;
+            stream.write_all(chunk).await?;
+        }
+        // A zero-length chunk marks the end of the stream
+        stream.write_all(&0u32.to_be_bytes()).await?;
+        stream.flush().await?;
+
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply).await?;
+
+        let reply = String::from_utf8_lossy(&reply);
+        let reply = reply.trim_end_matches('\0').trim();
+
+        // Reply format: `stream: OK`, `stream: <signature> FOUND` or `<message> ERROR`
+        if let Some(result) = reply.strip_prefix("stream: ") {
+            if result == "OK" {
+                return Ok(ScanVerdict::Clean);
+            } else if let Some(signature) = result.strip_suffix(" FOUND") {
+                return Ok(ScanVerdict::Infected(signature.to_owned()));
+            }
+        }
+
+        Err(eyre!("Unexpected reply from clamd: {}", reply))
+    }
+}
+
+impl Scanner for ClamdScanner {
+    fn scan<'a>(&'a self, data: &'a [u8]) -> ScanFuture<'a> {
+        Box::pin(self.scan_stream(data))
+    }
+}
+
+/// Decides what happens to an upload after it is scanned
+pub enum ScanPolicy {
+    /// The file can be published
+    Accept,
+    /// The file is infected and must be quarantined
+    Quarantine(String),
+    /// The scanner was unavailable and the scanner is configured to fail closed
+    Reject,
+}
+
+/// Scans a file using the given scanner and decides what to do with it.
+///
+/// If the scanner is unavailable, the file is accepted if `fail_open` is set and rejected otherwise.
+pub async fn scan_file(scanner: &dyn Scanner, data: &[u8], fail_open: bool) -> ScanPolicy {
+    match scanner.scan(data).await {
+        Ok(ScanVerdict::Clean) => ScanPolicy::Accept,
+        Ok(ScanVerdict::Infected(signature)) => ScanPolicy::Quarantine(signature),
+        Err(e) => {
+            tracing::error!("Malware scanner unavailable: {}", e);
+
+            if fail_open {
+                ScanPolicy::Accept
+            } else {
+                ScanPolicy::Reject
+            }
+        }
+    }
+}
+
+/// Returns the scanner configured in the environment variables. A [`ClamdScanner`] if `CLAMD_SOCKET_PATH` is set, else a [`NoopScanner`].
+pub fn get_scanner(env_vars: &EnvVars) -> Box<dyn Scanner> {
+    if env_vars.clamd_socket_path.is_empty() {
+        Box::new(NoopScanner)
+    } else {
+        Box::new(ClamdScanner::new(PathBuf::from(
+            &env_vars.clamd_socket_path,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::UnixListener;
+
+    use super::*;
+
+    /// A scanner that always fails, as if the daemon were unavailable
+    struct UnavailableScanner;
+
+    impl Scanner for UnavailableScanner {
+        fn scan<'a>(&'a self, _data: &'a [u8]) -> ScanFuture<'a> {
+            Box::pin(async { Err(eyre!("connection refused")) })
+        }
+    }
+
+    /// A scanner that always finds the given signature
+    struct InfectedScanner(&'static str);
+
+    impl Scanner for InfectedScanner {
+        fn scan<'a>(&'a self, _data: &'a [u8]) -> ScanFuture<'a> {
+            Box::pin(async { Ok(ScanVerdict::Infected(self.0.to_owned())) })
+        }
+    }
+
+    #[tokio::test]
+    async fn clean_files_are_accepted() {
+        assert!(matches!(
+            scan_file(&NoopScanner, b"%PDF", false).await,
+            ScanPolicy::Accept
+        ));
+    }
+
+    #[tokio::test]
+    async fn infected_files_are_quarantined_even_if_failing_open() {
+        let policy = scan_file(&InfectedScanner("Eicar-Signature"), b"%PDF", true).await;
+        assert!(
+            matches!(policy, ScanPolicy::Quarantine(signature) if signature == "Eicar-Signature")
+        );
+    }
+
+    #[tokio::test]
+    async fn unavailable_scanner_follows_fail_open() {
+        assert!(matches!(
+            scan_file(&UnavailableScanner, b"%PDF", true).await,
+            ScanPolicy::Accept
+        ));
+        assert!(matches!(
+            scan_file(&UnavailableScanner, b"%PDF", false).await,
+            ScanPolicy::Reject
+        ));
+    }
+
+    /// Starts a fake clamd daemon that reads one `INSTREAM` command and sends the given reply. Returns the scanner connected to it and the data received by the daemon.
+    async fn fake_clamd(
+        reply: &'static [u8],
+    ) -> (
+        tempfile::TempDir,
+        ClamdScanner,
+        tokio::task::JoinHandle<Vec<u8>>,
+    ) {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("clamd.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        let daemon = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            let mut command = [0u8; 10];
+            stream.read_exact(&mut command).await.unwrap();
+            assert_eq!(&command, b"zINSTREAM\0");
+
+            let mut received = Vec::new();
+            loop {
+                let mut length = [0u8; 4];
+                stream.read_exact(&mut length).await.unwrap();
+                let length = u32::from_be_bytes(length) as usize;
+                if length == 0 {
+                    break;
+                }
+
+                let mut chunk = vec![0u8; length];
+                stream.read_exact(&mut chunk).await.unwrap();
+                received.extend(chunk);
+            }
+
+            stream.write_all(reply).await.unwrap();
+            received
+        });
+
+        (dir, ClamdScanner::new(socket_path), daemon)
+    }
+
+    #[tokio::test]
+    async fn clamd_streams_the_file_in_chunks() {
+        let data = vec![7u8; CLAMD_CHUNK_SIZE * 2 + 5];
+        let (_dir, scanner, daemon) = fake_clamd(b"stream: OK\0").await;
+
+        assert!(matches!(
+            scanner.scan(&data).await.unwrap(),
+            ScanVerdict::Clean
+        ));
+        assert_eq!(daemon.await.unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn clamd_reports_the_signature() {
+        let (_dir, scanner, _daemon) = fake_clamd(b"stream: Eicar-Signature FOUND\0").await;
+
+        assert!(matches!(
+            scanner.scan(b"X5O!P%@AP").await.unwrap(),
+            ScanVerdict::Infected(signature) if signature == "Eicar-Signature"
+        ));
+    }
+
+    #[tokio::test]
+    async fn clamd_errors_are_not_verdicts() {
+        let (_dir, scanner, _daemon) = fake_clamd(b"INSTREAM size limit exceeded. ERROR\0").await;
+
+        assert!(scanner.scan(b"%PDF").await.is_err());
+    }
+}
@@ -0,0 +1,82 @@
+//! Statistics about the archive and the review queue, shown to the admins.
+
+use color_eyre::eyre::eyre;
+use serde::Serialize;
+
+/// The length of the periods the time series statistics are grouped into
+pub enum StatsPeriod {
+    Day,
+    Week,
+}
+
+impl TryFrom<&str> for StatsPeriod {
+    type Error = color_eyre::eyre::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "day" => Ok(StatsPeriod::Day),
+            "week" => Ok(StatsPeriod::Week),
+            _ => Err(eyre!("Error parsing stats period: Invalid value.")),
+        }
+    }
+}
+
+impl From<&StatsPeriod> for String {
+    fn from(value: &StatsPeriod) -> Self {
+        match value {
+            StatsPeriod::Day => "day".into(),
+            StatsPeriod::Week => "week".into(),
+        }
+    }
+}
+
+impl StatsPeriod {
+    /// The length of the period in days
+    pub fn days(&self) -> i64 {
+        match self {
+            StatsPeriod::Day => 1,
+            StatsPeriod::Week => 7,
+        }
+    }
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+/// The activity in a single period
+pub struct PeriodStats {
+    /// The start of the period
+    pub period_start: chrono::NaiveDateTime,
+    /// Number of papers uploaded (not including library papers)
+    pub uploads: i64,
+    /// Number of papers approved by the reviewers
+    pub approvals: i64,
+    /// Number of papers waiting for review at the end of the period
+    pub queue_size: i64,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+/// The number of papers with a particular value of a field
+pub struct CountStats {
+    pub key: String,
+    pub count: i64,
+}
+
+#[derive(Serialize)]
+/// The response format for the stats endpoint
+pub struct Stats {
+    /// Number of papers waiting for review right now
+    pub queue_size: i64,
+    /// Uploads, approvals, and the queue size in each period, oldest first
+    pub timeline: Vec<PeriodStats>,
+    /// Median time (in seconds) from upload to approval of the papers approved in the requested range. `None` if no papers were approved.
+    pub median_approval_seconds: Option<f64>,
+    /// Number of papers approved by each reviewer (of all time), most first
+    pub approvals_by_reviewer: Vec<CountStats>,
+    /// Number of published papers from the library and uploaded by users
+    pub by_source: Vec<CountStats>,
+    /// Number of published papers of each exam
+    pub by_exam: Vec<CountStats>,
+    /// Number of published papers of each year, latest first
+    pub by_year: Vec<CountStats>,
+    /// Number of published papers of each department, by the department code (see [`crate::departments`]) or the two letter prefix of the course code if the department is not known
+    pub by_department: Vec<CountStats>,
+}
@@ -0,0 +1,27 @@
+//! Tags on question papers, such as topics (`graph theory`) or `open book`. Tags are managed by the admins and can be used to filter the search results.
+
+use serde::Serialize;
+
+/// Maximum number of characters in a tag name
+pub const MAX_TAG_NAME_CHARS: usize = 50;
+
+#[derive(Serialize, sqlx::FromRow)]
+/// A tag that can be added to papers
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    /// The username of the admin who created the tag
+    pub created_by: String,
+    pub created_at: chrono::NaiveDateTime,
+    /// The number of published papers with this tag
+    pub paper_count: i64,
+}
+
+/// Normalizes a tag name: converts it to lowercase, trims it, and replaces any whitespace with a single space. Tags are always stored and matched by their normalized names.
+pub fn normalize_tag_name(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
@@ -33,6 +33,7 @@ function AdminDashboard() {
 	const [unapprovedPapers, setUnapprovedPapers] = useState<
 		IAdminDashboardQP[]
 	>([]);
+	const [totalUnapproved, setTotalUnapproved] = useState<number>(0);
 	const [numUniqueCourseCodes, setNumUniqueCourseCodes] = useState<number>(0);
 	const [awaitingResponse, setAwaitingResponse] = useState<boolean>(false);
 	const [ocrDetails, setOcrDetails] = useState<
@@ -75,6 +76,7 @@ function AdminDashboard() {
 				if (selectedQPaper && selectedQPaper.type === "unapproved") {
 					newPapers[selectedQPaper.index] = {
 						...qp,
+						version: response.data.version,
 					};
 				} else {
 					return papers;
@@ -94,11 +96,12 @@ function AdminDashboard() {
 		const papers = await makeRequest("unapproved", "get", null, auth.jwt);
 
 		if (papers.status === "success") {
-			setUnapprovedPapers(papers.data);
-			setOcrRequests(papers.data.slice(0, 20));
+			setUnapprovedPapers(papers.data.papers);
+			setTotalUnapproved(papers.data.total_unfiltered);
+			setOcrRequests(papers.data.papers.slice(0, 20));
 			setNumUniqueCourseCodes(
 				// Make an array of course codes
-				papers.data
+				papers.data.papers
 					.map((paper) => paper.course_code)
 					.filter(
 						// Keep unqiue values
@@ -269,7 +272,9 @@ function AdminDashboard() {
 						<div className="side-panel">
 							<p>
 								<b>Unapproved papers</b>:{" "}
-								{unapprovedPapers.length}
+								{unapprovedPapers.length < totalUnapproved
+									? `${unapprovedPapers.length} of ${totalUnapproved}`
+									: totalUnapproved}
 							</p>
 							<p>
 								<b>Unique Course Codes</b>:{" "}
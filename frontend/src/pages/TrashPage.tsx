@@ -26,7 +26,7 @@ function TrashPage() {
 		const papers = await makeRequest("trash", "get", null, auth.jwt);
 
 		if (papers.status === "success") {
-			setTrashPapers(papers.data.slice(0, 40));
+			setTrashPapers(papers.data.papers.slice(0, 40));
 		}
 
 		setAwaitingResponse(false);
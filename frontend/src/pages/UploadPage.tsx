@@ -6,10 +6,25 @@ import toast from "react-hot-toast";
 import { IQuestionPaperFile } from "../types/question_paper";
 import { isQPValid } from "../utils/validateInput";
 import { makeRequest } from "../utils/backend";
+import { IUploadStatus } from "../types/backend";
 import { sanitizeQP } from "../utils/autofillData";
 import "./styles/upload_page.scss";
 import { UploadForm } from "../components/Upload/UploadForm";
 
+const UPLOAD_JOB_POLL_INTERVAL_MS = 1000;
+
+/** Polls an upload job until it is done and returns the status of each file, or `null` if the job status could not be fetched. */
+async function waitForUploadJob(job_token: string): Promise<IUploadStatus[] | null> {
+    while (true) {
+        const response = await makeRequest(`upload/jobs/${job_token}`, "get");
+
+        if (response.status !== "success") return null;
+        if (response.data.status === "done") return response.data.files;
+
+        await new Promise((resolve) => setTimeout(resolve, UPLOAD_JOB_POLL_INTERVAL_MS));
+    }
+}
+
 export default function UploadPage() {
     let MAX_UPLOAD_LIMIT = parseInt(import.meta.env.VITE_MAX_UPLOAD_LIMIT);
     if (isNaN(MAX_UPLOAD_LIMIT) || MAX_UPLOAD_LIMIT < 1) {
@@ -81,7 +96,13 @@ export default function UploadPage() {
                 const response = await makeRequest("upload", "post", formData);
 
                 if (response.status === "success") {
-                    const upload_results = response.data;
+                    const upload_results = await waitForUploadJob(response.data.job_token);
+
+                    if (upload_results === null) {
+                        toast.error("Failed to get the upload status. Please try again later.");
+                        setUploading(false);
+                        return false;
+                    }
 
                     for (const result of upload_results) {
                         if (result.status === "success") {
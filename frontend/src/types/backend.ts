@@ -5,7 +5,7 @@ export type AllowedBackendMethods = "get" | "post";
 export interface IOkResponse<T> {
 	status: "success";
 	message: string;
-	status_code: 200;
+	status_code: 200 | 202;
 	data: T;
 }
 
@@ -17,6 +17,30 @@ export interface IErrorResponse {
 
 export type BackendResponse<T> = IOkResponse<T> | IErrorResponse;
 
+export interface IUploadStatus {
+	filename: string;
+	status: "pending" | "processing" | "success" | "error" | "rejected";
+	message: string;
+	paper_id: number | null;
+}
+
+export interface IUploadJob {
+	token: string;
+	atomic: boolean;
+	status: "pending" | "processing" | "done";
+	created_at: string;
+	finished_at: string | null;
+	files: IUploadStatus[];
+}
+
+
+export interface IPaperList {
+	papers: IAdminDashboardQP[];
+	page: number;
+	per_page: number;
+	total: number;
+	total_unfiltered: number;
+}
 
 export interface IEndpointTypes {
 	search: {
@@ -36,11 +60,11 @@ export interface IEndpointTypes {
 	},
 	unapproved: {
 		request: null,
-		response: IAdminDashboardQP[]
+		response: IPaperList
 	},
 	trash: {
 		request: null,
-		response: IAdminDashboardQP[]
+		response: IPaperList
 	},
 	details: {
 		request: { id: number },
@@ -49,10 +73,12 @@ export interface IEndpointTypes {
 	upload: {
 		request: FormData,
 		response: {
-			filename: string;
-			status: "success" | "error";
-			message: string;
-		}[]
+			job_token: string;
+		}
+	},
+	[upload_job: `upload/jobs/${string}`]: {
+		request: null,
+		response: IUploadJob
 	},
 	edit: {
 		request: {
@@ -64,11 +90,10 @@ export interface IEndpointTypes {
 			exam?: string,
 			note?: string,
 			approve_status?: boolean,
-			replace: number[]
+			replace: number[],
+			version: number
 		},
-		response: {
-			id: number;
-		}
+		response: IAdminDashboardQP
 	},
 	delete: {
 		request: {
@@ -91,6 +116,7 @@ export interface IEndpointTypes {
 		response: {
 			username: string;
 			token: string;
+			role: "viewer" | "reviewer" | "maintainer" | "owner";
 		}
 	},
 	similar: {
@@ -23,6 +23,7 @@ export interface ISearchResult extends IQuestionPaper {
 export interface IAdminDashboardQP extends ISearchResult {
 	upload_timestamp: string;
 	approve_status: boolean;
+	version: number;
 }
 
 export interface IQuestionPaperFile extends IQuestionPaper {